@@ -2,7 +2,7 @@
 
 use std::collections::BTreeSet;
 
-use coalesced_intervals::CoalescedIntervals;
+use coalesced_intervals::{CoalescedIntervals, Interval};
 
 use libfuzzer_sys::{arbitrary::{Arbitrary, Error, Unstructured}, fuzz_target};
 
@@ -37,24 +37,22 @@ impl<'a> Arbitrary<'a> for IntervalsToInsert {
     }
 }
 
-fn check_vector(v: &Vec<(i8, i8)>) {
+fn check_vector(v: &Vec<Interval<i8>>) {
     // Check the vector is sorted.
     let mut v_clone = v.clone();
     v_clone.sort();
     assert_eq!(*v, v_clone);
 
     for i in 0..v.len() {
-        let (start, limit) = v[i];
-        assert!(limit > start);
+        assert!(!v[i].is_empty());
         if i > 0 {
-            let (_prev_start, prev_limit) = v[i-1];
             // This start should be > the prev limit or they should have been coalesced.
-            assert!(start > prev_limit);
+            assert!(v[i].start() > v[i-1].limit());
+            assert!(!v[i].abuts(&v[i-1]) && !v[i].overlaps(&v[i-1]));
         }
         if i+1 < v.len() {
-            let (next_start, _next_limit) = v[i+1];
             // This limit should be < the prev start or they should have been coalesced.
-            assert!(limit < next_start);
+            assert!(v[i].limit() < v[i+1].start());
         }
     }
 }
@@ -71,6 +69,7 @@ fuzz_target!(|ivals: IntervalsToInsert| {
     }
 
     coalesced.check_invariants();
+    assert_eq!(coalesced.validate(), Ok(()));
 
     // Check the "get_interval_containing" result is appropriately some/none for every value we
     // determined is included in the (small) range under test.
@@ -100,10 +99,10 @@ fuzz_target!(|ivals: IntervalsToInsert| {
                 }
                 break;
             },
-            Some((ival_start, ival_limit)) => {
-                find_start_from = ival_start + 1;
-                last_limit_seen = ival_limit;
-                seen.push((ival_start, ival_limit));
+            Some(ival) => {
+                find_start_from = ival.start() + 1;
+                last_limit_seen = *ival.limit();
+                seen.push(ival);
             }
         }
     }
@@ -114,9 +113,48 @@ fuzz_target!(|ivals: IntervalsToInsert| {
     // from" based traversal.
     assert_eq!(v, seen);
 
+    // Check the nearest-edge queries against a brute force scan over the sorted intervals for
+    // every value in the domain.
+    for value in i8::MIN..=i8::MAX {
+        assert_eq!(
+            coalesced.get_first_start_from(value),
+            v.iter().copied().find(|ival| *ival.start() >= value)
+        );
+        assert_eq!(
+            coalesced.get_last_start_before(value),
+            v.iter().copied().rev().find(|ival| *ival.start() <= value)
+        );
+        assert_eq!(
+            coalesced.get_first_limit_from(value),
+            v.iter().copied().find(|ival| *ival.limit() >= value)
+        );
+        assert_eq!(
+            coalesced.get_first_limit_before(value),
+            v.iter().copied().rev().find(|ival| *ival.limit() < value)
+        );
+    }
+
+    // Check `contains_partial` and `contains_full` against a brute force scan for every window in
+    // the domain.
+    for start in i8::MIN..=i8::MAX {
+        for limit in start..=i8::MAX {
+            let (expected_partial, expected_full) = if start == limit {
+                let included = ivals.included.contains(&start);
+                (included, included)
+            } else {
+                (
+                    v.iter().any(|ival| ival.overlaps(&Interval::new(start, limit))),
+                    v.iter().any(|ival| *ival.start() <= start && limit <= *ival.limit()),
+                )
+            };
+            assert_eq!(coalesced.contains_partial(start, limit), expected_partial, "[{}, {})", start, limit);
+            assert_eq!(coalesced.contains_full(start, limit), expected_full, "[{}, {})", start, limit);
+        }
+    }
+
     // Checks that all the intervals in the vector are "partial overlaps".
-    for (start, limit) in v.iter() {
-        assert!(coalesced.contains_partial(*start, *limit));
+    for ival in v.iter() {
+        assert!(coalesced.contains_partial(*ival.start(), *ival.limit()));
     }
 
     log::debug!("v: {:?}", v);
@@ -126,14 +164,22 @@ fuzz_target!(|ivals: IntervalsToInsert| {
     if v.is_empty() {
         return;
     }
-    let mut holes = CoalescedIntervals::new();
+    let mut expected_holes = CoalescedIntervals::new();
     for i in 0..v.len()-1 {
-        let (_, i_limit) = v[i];
-        let (ip1_start, _) = v[i+1];
-        holes.add(i_limit, ip1_start);
+        expected_holes.add(*v[i].limit(), *v[i+1].start());
     }
+
+    // The crate's own notion of the holes between the first start and the last limit should
+    // match.
+    let first_start = *v[0].start();
+    let last_limit = *v[v.len()-1].limit();
+    let holes = coalesced.complement_within(first_start, last_limit);
+    holes.check_invariants();
+    assert_eq!(holes.to_vec(), expected_holes.to_vec());
+    assert_eq!(coalesced.gaps(first_start, last_limit).collect::<Vec<_>>(), holes.to_vec());
+
     // Make sure the original doesn't contain any of these holes.
-    for (hole_start, hole_limit) in holes.to_vec() {
-        assert!(!coalesced.contains_partial(hole_start, hole_limit));
+    for hole in holes.to_vec() {
+        assert!(!coalesced.contains_partial(*hole.start(), *hole.limit()));
     }
 });
@@ -0,0 +1,252 @@
+use crate::{CoalescedIntervalMap, CoalescedIntervals, IntervalError};
+
+/// Reference-counted coverage: a multiset of `[start, limit)` intervals that tracks how many times
+/// each point is covered.
+///
+/// Each [`add`](Self::add) increments the coverage depth over its interval and each
+/// [`remove`](Self::remove) decrements it, so a point stays covered until every add that overlaps
+/// it has been removed. For example, if I add `[0, 4)` and `[2, 6)` and then remove `[0, 4)`, the
+/// point `2` is still covered (at depth 1) by the second add.
+///
+/// Implementation note: the depths are held in a [`CoalescedIntervalMap`], so runs of equal depth
+/// are coalesced and uncovered points (depth zero) are not stored at all.
+pub struct CoalescedCoverage<T> {
+    depths: CoalescedIntervalMap<T, usize>,
+}
+
+impl<T: std::cmp::Ord + Clone> Default for CoalescedCoverage<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: std::cmp::Ord + Clone> CoalescedCoverage<T> {
+    /// Creates a new (empty) coverage set.
+    pub fn new() -> Self {
+        CoalescedCoverage {
+            depths: CoalescedIntervalMap::new(),
+        }
+    }
+
+    /// Checks invariants for this data structure -- panics via `assert!` if there are internal
+    /// inconsistencies.
+    pub fn check_invariants(&self) {
+        self.depths.check_invariants();
+        for (_start, _limit, depth) in self.depths.iter() {
+            assert!(*depth > 0);
+        }
+    }
+
+    /// Increments the coverage depth over `[start, limit)`.
+    pub fn add(&mut self, start: T, limit: T) {
+        assert!(start <= limit);
+        self.depths.update_range(start, limit, |depth| {
+            Some(depth.map_or(1, |depth| depth + 1))
+        });
+    }
+
+    /// Fallible form of [`add`](Self::add): returns [`IntervalError::InvertedBounds`] instead of
+    /// panicking when `start > limit`.
+    pub fn try_add(&mut self, start: T, limit: T) -> Result<(), IntervalError<T>> {
+        IntervalError::check_bounds(&start, &limit)?;
+        self.add(start, limit);
+        Ok(())
+    }
+
+    /// Returns the first hole within `[start, limit)` (clipped to it), if any.
+    fn first_uncovered(&self, start: &T, limit: &T) -> Option<(T, T)> {
+        let mut cursor = start.clone();
+        for (run_start, run_limit, _depth) in
+            self.depths.iter_overlapping(start.clone(), limit.clone())
+        {
+            if run_start > cursor {
+                return Some((cursor, run_start));
+            }
+            cursor = run_limit;
+        }
+        if cursor < *limit {
+            Some((cursor, limit.clone()))
+        } else {
+            None
+        }
+    }
+
+    /// Decrements the coverage depth over `[start, limit)`; points whose depth drops to zero are
+    /// no longer covered.
+    ///
+    /// Panics if any part of `[start, limit)` is not currently covered, since that means it is
+    /// being removed more times than it was added.
+    pub fn remove(&mut self, start: T, limit: T) {
+        assert!(start <= limit);
+        if self.first_uncovered(&start, &limit).is_some() {
+            panic!("Attempted to remove coverage that was not present");
+        }
+        self.decrement(start, limit);
+    }
+
+    /// Fallible form of [`remove`](Self::remove): returns [`IntervalError::InvertedBounds`] when
+    /// `start > limit`, and [`IntervalError::NotCovered`] with the first uncovered hole when part
+    /// of `[start, limit)` is not covered. The coverage is left untouched on error.
+    pub fn try_remove(&mut self, start: T, limit: T) -> Result<(), IntervalError<T>> {
+        IntervalError::check_bounds(&start, &limit)?;
+        if let Some((hole_start, hole_limit)) = self.first_uncovered(&start, &limit) {
+            return Err(IntervalError::NotCovered {
+                start: hole_start,
+                limit: hole_limit,
+            });
+        }
+        self.decrement(start, limit);
+        Ok(())
+    }
+
+    /// Decrements the depth over `[start, limit)`, which must be fully covered.
+    fn decrement(&mut self, start: T, limit: T) {
+        self.depths.update_range(start, limit, |depth| match depth {
+            Some(1) => None,
+            Some(depth) => Some(depth - 1),
+            None => unreachable!(),
+        });
+    }
+
+    /// Returns the number of times `value` is covered; zero if it is not covered.
+    pub fn depth_at(&self, value: T) -> usize {
+        self.depths.get(value).copied().unwrap_or(0)
+    }
+
+    /// Returns an iterator over the `(start, limit, depth)` runs within the window `[lo, hi)`, in
+    /// sorted (ascending) order.
+    ///
+    /// Runs are clipped to the window, and uncovered holes (depth zero) are skipped.
+    pub fn iter_depth(&self, lo: T, hi: T) -> impl DoubleEndedIterator<Item = (T, T, usize)> + '_ {
+        assert!(lo <= hi);
+        // An empty window yields nothing, rather than the run containing the point.
+        let runs = (lo < hi).then(|| self.depths.iter_overlapping(lo.clone(), hi.clone()));
+        runs.into_iter()
+            .flatten()
+            .map(move |(start, limit, depth)| {
+                (
+                    std::cmp::max(start, lo.clone()),
+                    std::cmp::min(limit, hi.clone()),
+                    *depth,
+                )
+            })
+    }
+
+    /// Fallible form of [`iter_depth`](Self::iter_depth): returns
+    /// [`IntervalError::InvertedBounds`] instead of panicking when `lo > hi`.
+    pub fn try_iter_depth(
+        &self,
+        lo: T,
+        hi: T,
+    ) -> Result<impl DoubleEndedIterator<Item = (T, T, usize)> + '_, IntervalError<T>> {
+        IntervalError::check_bounds(&lo, &hi)?;
+        Ok(self.iter_depth(lo, hi))
+    }
+
+    /// Returns the set of points covered at least once.
+    pub fn covered(&self) -> CoalescedIntervals<T> {
+        let mut covered = CoalescedIntervals::new();
+        for (start, limit, _depth) in self.depths.iter() {
+            covered.add(start, limit);
+        }
+        covered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Overlapping adds keep points covered until each of them has been removed.
+    #[test]
+    fn test_overlapping_pins() {
+        let _ = env_logger::try_init();
+        let mut coverage = CoalescedCoverage::<i64>::new();
+        coverage.add(0, 4);
+        coverage.add(2, 6);
+        assert_eq!(coverage.depth_at(1), 1);
+        assert_eq!(coverage.depth_at(2), 2);
+        assert_eq!(coverage.depth_at(6), 0);
+        assert_eq!(coverage.covered().to_vec(), [(0, 6)]);
+
+        coverage.remove(0, 4);
+        assert_eq!(coverage.depth_at(1), 0);
+        assert_eq!(coverage.depth_at(2), 1);
+        assert_eq!(coverage.covered().to_vec(), [(2, 6)]);
+        coverage.check_invariants();
+
+        coverage.remove(2, 6);
+        assert!(coverage.covered().to_vec().is_empty());
+        coverage.check_invariants();
+    }
+
+    #[test]
+    fn test_iter_depth() {
+        let mut coverage = CoalescedCoverage::<i64>::new();
+        coverage.add(0, 4);
+        coverage.add(2, 6);
+        coverage.add(8, 10);
+        assert_eq!(
+            coverage.iter_depth(-1, 11).collect::<Vec<_>>(),
+            [(0, 2, 1), (2, 4, 2), (4, 6, 1), (8, 10, 1)]
+        );
+        assert_eq!(
+            coverage.iter_depth(3, 9).collect::<Vec<_>>(),
+            [(3, 4, 2), (4, 6, 1), (8, 9, 1)]
+        );
+        assert_eq!(coverage.iter_depth(3, 3).collect::<Vec<_>>(), []);
+        assert_eq!(coverage.iter_depth(6, 8).collect::<Vec<_>>(), []);
+
+        assert_eq!(
+            coverage.try_iter_depth(9, 3).err(),
+            Some(IntervalError::InvertedBounds { start: 9, limit: 3 })
+        );
+        assert_eq!(
+            coverage.try_iter_depth(3, 5).unwrap().collect::<Vec<_>>(),
+            [(3, 4, 2), (4, 5, 1)]
+        );
+    }
+
+    #[test]
+    fn test_try_remove() {
+        let mut coverage = CoalescedCoverage::<i64>::new();
+        assert_eq!(
+            coverage.try_add(2, 1),
+            Err(IntervalError::InvertedBounds { start: 2, limit: 1 })
+        );
+        assert_eq!(coverage.try_add(0, 2), Ok(()));
+        assert_eq!(coverage.try_add(3, 5), Ok(()));
+        assert_eq!(
+            coverage.try_remove(1, 4),
+            Err(IntervalError::NotCovered { start: 2, limit: 3 })
+        );
+        assert_eq!(coverage.depth_at(1), 1);
+        assert_eq!(coverage.try_remove(3, 4), Ok(()));
+        assert_eq!(coverage.covered().to_vec(), [(0, 2), (4, 5)]);
+    }
+
+    /// Nothing requires `T: Debug`.
+    #[test]
+    fn test_non_debug_keys() {
+        #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+        struct Key(u32);
+
+        let mut coverage = CoalescedCoverage::<Key>::new();
+        coverage.add(Key(0), Key(4));
+        coverage.add(Key(2), Key(6));
+        coverage.remove(Key(0), Key(4));
+        assert_eq!(coverage.depth_at(Key(3)), 1);
+        assert_eq!(coverage.iter_depth(Key(0), Key(8)).count(), 1);
+        coverage.check_invariants();
+    }
+
+    /// Removing coverage that was never added is a caller bug.
+    #[test]
+    #[should_panic(expected = "not present")]
+    fn test_remove_uncovered() {
+        let mut coverage = CoalescedCoverage::<i64>::new();
+        coverage.add(0, 2);
+        coverage.add(3, 5);
+        coverage.remove(1, 4);
+    }
+}
@@ -0,0 +1,800 @@
+use core::ops::{Bound, RangeBounds};
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+
+use crate::{CoalescedIntervals, Interval, IntervalError, Step};
+
+/// A position on the number line that falls between values, used as the endpoint type for
+/// intervals with closed, open or unbounded ends.
+///
+/// `Below(v)` sits just below `v` and `Above(v)` just above it, so every half-open
+/// `[start, limit)` of cuts describes an interval of values regardless of which of its ends are
+/// inclusive: `[0, 1]` is `[Below(0), Above(1))` and `(1, 2)` is `[Above(1), Below(2))`. Since
+/// those two share the cut `Above(1)` they abut and coalesce into `[0, 2)`, whereas `[0, 1)` (i.e.
+/// `[Below(0), Below(1))`) and `(1, 2)` leave the point `1` uncovered between them.
+///
+/// `BelowAll` and `AboveAll` sit beyond every value, for unbounded ends.
+///
+/// On a discrete domain more than one cut can fall between the same two values; see [`Domain`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Cut<T> {
+    BelowAll,
+    Below(T),
+    Above(T),
+    AboveAll,
+}
+
+impl<T: Ord> Ord for Cut<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Cut::BelowAll, Cut::BelowAll) | (Cut::AboveAll, Cut::AboveAll) => Ordering::Equal,
+            (Cut::BelowAll, _) | (_, Cut::AboveAll) => Ordering::Less,
+            (_, Cut::BelowAll) | (Cut::AboveAll, _) => Ordering::Greater,
+            (Cut::Below(lhs) | Cut::Above(lhs), Cut::Below(rhs) | Cut::Above(rhs)) => {
+                // For the same value, `Below` comes first.
+                let is_above = |cut: &Self| matches!(cut, Cut::Above(_));
+                lhs.cmp(rhs).then(is_above(self).cmp(&is_above(other)))
+            }
+        }
+    }
+}
+
+impl<T: Ord> PartialOrd for Cut<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Clone> Cut<T> {
+    /// Returns the cut that starts an interval whose start bound is `bound`.
+    pub fn from_start_bound(bound: Bound<&T>) -> Self {
+        match bound {
+            Bound::Included(value) => Cut::Below(value.clone()),
+            Bound::Excluded(value) => Cut::Above(value.clone()),
+            Bound::Unbounded => Cut::BelowAll,
+        }
+    }
+
+    /// Returns the cut that limits an interval whose end bound is `bound`.
+    pub fn from_limit_bound(bound: Bound<&T>) -> Self {
+        match bound {
+            Bound::Included(value) => Cut::Above(value.clone()),
+            Bound::Excluded(value) => Cut::Below(value.clone()),
+            Bound::Unbounded => Cut::AboveAll,
+        }
+    }
+
+    /// Returns the start bound of an interval that starts at this cut.
+    pub fn as_start_bound(&self) -> Bound<T> {
+        match self {
+            Cut::Below(value) => Bound::Included(value.clone()),
+            Cut::Above(value) => Bound::Excluded(value.clone()),
+            Cut::BelowAll | Cut::AboveAll => Bound::Unbounded,
+        }
+    }
+
+    /// Returns the end bound of an interval that is limited by this cut.
+    pub fn as_limit_bound(&self) -> Bound<T> {
+        match self {
+            Cut::Below(value) => Bound::Excluded(value.clone()),
+            Cut::Above(value) => Bound::Included(value.clone()),
+            Cut::BelowAll | Cut::AboveAll => Bound::Unbounded,
+        }
+    }
+}
+
+/// Says how to spell each [`Cut`] over values of `T` canonically; this is the `D` parameter of
+/// [`CoalescedRanges`].
+///
+/// On a discrete domain there can be no value between two cuts that differ; e.g. for integers
+/// `Above(1)` and `Below(2)` both fall between `1` and `2`, and `Below(i64::MIN)` falls below every
+/// value just like `BelowAll`. Normalizing such cuts to one spelling is what lets `[0, 1]` and
+/// `[2, 3]` coalesce, and lets a range like `(1, 2)` that holds no values be recognized as empty.
+///
+/// Every [`Step`] type (including [`Duration`](std::time::Duration), in nanoseconds) is its own
+/// domain, normalizing `Above(v)` to `Below(v + 1)` with the cuts beyond the minimum and maximum
+/// values becoming `BelowAll` and `AboveAll`. `String` and `Vec<u8>` are too: the value just after
+/// `s` is `s` followed by a zero, and the empty string is the minimum. [`NotNan`](crate::NotNan) is
+/// treated as though it were dense, so only the cuts beyond the infinities are normalized. Any
+/// other `Ord` type (e.g. `Instant`, whose resolution is unspecified) can use [`Dense`], which
+/// leaves every cut as is. Since the domain is a separate type, a crate can also implement
+/// `Domain<T>` for its own marker type to normalize a `T` defined elsewhere.
+pub trait Domain<T> {
+    /// Returns the canonical spelling of `cut`.
+    fn normalize(cut: Cut<T>) -> Cut<T>;
+}
+
+/// [`Domain`] for any `Ord` type that leaves every cut as given; e.g.
+/// `CoalescedRanges<BigInt, Dense>`.
+///
+/// This is exact for dense domains, where a value lies between any two different cuts. On a
+/// discrete domain, ranges with no value between them do not coalesce, and a range that holds no
+/// values (e.g. `(1, 2)` on integers) is held as though it were not empty.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Dense;
+
+impl<T: Ord> Domain<T> for Dense {
+    fn normalize(cut: Cut<T>) -> Cut<T> {
+        cut
+    }
+}
+
+impl<T: Step> Domain<T> for T {
+    fn normalize(cut: Cut<T>) -> Cut<T> {
+        match cut {
+            Cut::Below(value) if value.predecessor().is_none() => Cut::BelowAll,
+            Cut::Above(value) => match value.successor() {
+                Some(next) => Cut::Below(next),
+                None => Cut::AboveAll,
+            },
+            cut => cut,
+        }
+    }
+}
+
+impl Domain<String> for String {
+    fn normalize(cut: Cut<String>) -> Cut<String> {
+        match cut {
+            Cut::Below(value) if value.is_empty() => Cut::BelowAll,
+            Cut::Above(mut value) => {
+                value.push('\0');
+                Cut::Below(value)
+            }
+            cut => cut,
+        }
+    }
+}
+
+impl Domain<Vec<u8>> for Vec<u8> {
+    fn normalize(cut: Cut<Vec<u8>>) -> Cut<Vec<u8>> {
+        match cut {
+            Cut::Below(value) if value.is_empty() => Cut::BelowAll,
+            Cut::Above(mut value) => {
+                value.push(0);
+                Cut::Below(value)
+            }
+            cut => cut,
+        }
+    }
+}
+
+/// Interval set over values of `T` whose intervals may have closed, open or unbounded ends; see
+/// [`Cut`].
+///
+/// The `*_range` methods accept any [`RangeBounds`] (`a..b`, `a..=b`, `..b`, `a..`, ...) and
+/// normalize their cuts for the [`Domain`] `D`, which defaults to `T` itself; e.g. any [`Step`]
+/// type. So on a discrete domain the bounds they report are always `Included` starts and
+/// `Excluded` limits, and an end at the minimum or maximum value is reported as `Unbounded`; e.g.
+/// `200..=255` on `u8` is reported as `200..`. Endpoint types that are not a domain of their own
+/// can use [`Dense`].
+///
+/// Implementation note: the ranges are held as a [`CoalescedIntervals`] of normalized
+/// `[start, limit)` cut pairs, which [`as_intervals`](Self::as_intervals) exposes for the other
+/// queries.
+pub struct CoalescedRanges<T, D = T> {
+    cuts: CoalescedIntervals<Cut<T>>,
+    domain: PhantomData<fn() -> D>,
+}
+
+/// Returns the normalized `[start, limit)` cuts for `range`, or `None` if the range holds no
+/// values.
+///
+/// Panics if the range is inverted; e.g. `3..1`. Ranges like `(1, 1)` whose start and end
+/// values are equal are merely empty.
+fn range_to_cuts<T: Clone + Ord, D: Domain<T>>(
+    range: &impl RangeBounds<T>,
+) -> Option<(Cut<T>, Cut<T>)> {
+    assert!(check_range_bounds(range).is_ok());
+    let start = D::normalize(Cut::from_start_bound(range.start_bound()));
+    let limit = D::normalize(Cut::from_limit_bound(range.end_bound()));
+    if start < limit {
+        Some((start, limit))
+    } else {
+        None
+    }
+}
+
+/// The start and end bounds of a range, as reported by the `*_range` methods.
+type Bounds<T> = (Bound<T>, Bound<T>);
+
+/// Returns `InvertedBounds` if `range` is inverted; e.g. `3..1`.
+fn check_range_bounds<T: Clone + Ord>(range: &impl RangeBounds<T>) -> Result<(), IntervalError<T>> {
+    match (range.start_bound(), range.end_bound()) {
+        (
+            Bound::Included(start) | Bound::Excluded(start),
+            Bound::Included(end) | Bound::Excluded(end),
+        ) => IntervalError::check_bounds(start, end),
+        _ => Ok(()),
+    }
+}
+
+/// Converts a `[start, limit)` cut pair back into the bounds it describes.
+fn cuts_to_bounds<T: Clone>(interval: Interval<Cut<T>>) -> Bounds<T> {
+    (
+        interval.start().as_start_bound(),
+        interval.limit().as_limit_bound(),
+    )
+}
+
+impl<T: Ord + Clone, D: Domain<T>> Default for CoalescedRanges<T, D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord + Clone + std::fmt::Debug, D: Domain<T>> CoalescedRanges<T, D> {
+    /// Checks interval invariants for this data structure -- panics if there are internal
+    /// inconsistencies, including cuts that are not normalized for the domain.
+    pub fn check_invariants(&self) {
+        self.cuts.check_invariants();
+        for interval in self.cuts.iter() {
+            for cut in [interval.start(), interval.limit()] {
+                assert!(
+                    D::normalize(cut.clone()) == *cut,
+                    "cut {:?} was not normalized",
+                    cut
+                );
+            }
+        }
+    }
+}
+
+impl<T: Ord + Clone, D: Domain<T>> CoalescedRanges<T, D> {
+    /// Creates a new (empty) set of ranges.
+    pub fn new() -> Self {
+        Self::from_intervals(CoalescedIntervals::new())
+    }
+
+    fn from_intervals(cuts: CoalescedIntervals<Cut<T>>) -> Self {
+        CoalescedRanges {
+            cuts,
+            domain: PhantomData,
+        }
+    }
+
+    /// Returns the underlying `[start, limit)` cut pairs, e.g. for the queries that are not
+    /// repeated on this type.
+    pub fn as_intervals(&self) -> &CoalescedIntervals<Cut<T>> {
+        &self.cuts
+    }
+
+    /// Adds the values in `range` to the current interval set.
+    ///
+    /// Returns the sub-ranges that were not covered before this call, as with
+    /// [`CoalescedIntervals::add`].
+    pub fn add_range(&mut self, range: impl RangeBounds<T>) -> Vec<Bounds<T>> {
+        match range_to_cuts::<T, D>(&range) {
+            Some((start, limit)) => self
+                .cuts
+                .add(start, limit)
+                .into_iter()
+                .map(cuts_to_bounds)
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Fallible form of [`add_range`](Self::add_range): returns
+    /// [`IntervalError::InvertedBounds`] instead of panicking when `range` is inverted.
+    pub fn try_add_range(
+        &mut self,
+        range: impl RangeBounds<T>,
+    ) -> Result<Vec<Bounds<T>>, IntervalError<T>> {
+        check_range_bounds(&range)?;
+        Ok(self.add_range(range))
+    }
+
+    /// Removes the values in `range` from the current interval set.
+    ///
+    /// Returns the sub-ranges that were actually removed, as with [`CoalescedIntervals::remove`].
+    pub fn remove_range(&mut self, range: impl RangeBounds<T>) -> Vec<Bounds<T>> {
+        match range_to_cuts::<T, D>(&range) {
+            Some((start, limit)) => self
+                .cuts
+                .remove(start, limit)
+                .into_iter()
+                .map(cuts_to_bounds)
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Fallible form of [`remove_range`](Self::remove_range): returns
+    /// [`IntervalError::InvertedBounds`] instead of panicking when `range` is inverted.
+    pub fn try_remove_range(
+        &mut self,
+        range: impl RangeBounds<T>,
+    ) -> Result<Vec<Bounds<T>>, IntervalError<T>> {
+        check_range_bounds(&range)?;
+        Ok(self.remove_range(range))
+    }
+
+    /// Returns whether any of the values in `range` are in the current interval set.
+    ///
+    /// As with [`CoalescedIntervals::contains_partial`], an empty `a..a` range asks whether the
+    /// point `a` is contained; any other range that holds no values contains nothing.
+    pub fn contains_partial_range(&self, range: impl RangeBounds<T>) -> bool {
+        match range_to_cuts::<T, D>(&range) {
+            Some((start, limit)) => self.cuts.contains_partial(start, limit),
+            None => match (range.start_bound(), range.end_bound()) {
+                (Bound::Included(value), Bound::Excluded(end)) if value == end => {
+                    self.contains(value.clone())
+                }
+                _ => false,
+            },
+        }
+    }
+
+    /// Fallible form of [`contains_partial_range`](Self::contains_partial_range): returns
+    /// [`IntervalError::InvertedBounds`] instead of panicking when `range` is inverted.
+    pub fn try_contains_partial_range(
+        &self,
+        range: impl RangeBounds<T>,
+    ) -> Result<bool, IntervalError<T>> {
+        check_range_bounds(&range)?;
+        Ok(self.contains_partial_range(range))
+    }
+
+    /// Returns whether `value` is in the current interval set.
+    pub fn contains(&self, value: T) -> bool {
+        self.get_range_containing(value).is_some()
+    }
+
+    /// Returns the bounds of the interval that contains `value`, or `None` if there is none.
+    pub fn get_range_containing(&self, value: T) -> Option<Bounds<T>> {
+        // The interval holds `value` exactly when it holds the cut just below it.
+        self.cuts
+            .get_interval_containing(Cut::Below(value))
+            .map(cuts_to_bounds)
+    }
+
+    /// Returns the bounds of the first interval that starts at-or-after `value`; i.e. whose start
+    /// bound is `Included(v)` or `Excluded(v)` for some `v >= value`.
+    ///
+    /// If there is no such interval, `None` is returned; in particular an interval with an
+    /// unbounded start is never "after" any value other than the minimum of a discrete domain.
+    pub fn get_first_range_from(&self, value: T) -> Option<Bounds<T>> {
+        self.cuts
+            .get_first_start_from(D::normalize(Cut::Below(value)))
+            .map(cuts_to_bounds)
+    }
+
+    /// Returns the complement of this interval set over the whole line.
+    ///
+    /// The complement of an empty set is the unbounded `(-inf, +inf)`, and vice versa. On a
+    /// discrete domain a set covering every value (e.g. `0..=255` on `u8`) is the whole line, so
+    /// its complement is empty.
+    pub fn complement(&self) -> Self {
+        Self::from_intervals(self.cuts.complement_within(Cut::BelowAll, Cut::AboveAll))
+    }
+
+    /// Returns the set of values covered by either `self` or `other`.
+    pub fn union(&self, other: &Self) -> Self {
+        Self::from_intervals(self.cuts.union(&other.cuts))
+    }
+
+    /// Returns the set of values covered by both `self` and `other`.
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self::from_intervals(self.cuts.intersection(&other.cuts))
+    }
+
+    /// Returns the set of values covered by `self` but not by `other`.
+    pub fn difference(&self, other: &Self) -> Self {
+        Self::from_intervals(self.cuts.difference(&other.cuts))
+    }
+
+    /// Returns the set of values covered by exactly one of `self` and `other`.
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        Self::from_intervals(self.cuts.symmetric_difference(&other.cuts))
+    }
+
+    /// Returns an iterator over the bounds of the intervals in sorted (ascending) order.
+    pub fn iter_ranges(&self) -> impl DoubleEndedIterator<Item = Bounds<T>> + '_ {
+        self.cuts.iter().map(cuts_to_bounds)
+    }
+
+    /// Converts the current interval set to a vector of bounds in sorted (ascending) order.
+    pub fn to_range_vec(&self) -> Vec<Bounds<T>> {
+        self.iter_ranges().collect()
+    }
+}
+
+// The domain is only a marker, so none of these require anything of `D`.
+
+impl<T: Clone, D> Clone for CoalescedRanges<T, D> {
+    fn clone(&self) -> Self {
+        CoalescedRanges {
+            cuts: self.cuts.clone(),
+            domain: PhantomData,
+        }
+    }
+}
+
+impl<T: PartialEq, D> PartialEq for CoalescedRanges<T, D> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cuts == other.cuts
+    }
+}
+
+impl<T: Eq, D> Eq for CoalescedRanges<T, D> {}
+
+impl<T: std::hash::Hash, D> std::hash::Hash for CoalescedRanges<T, D> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.cuts.hash(state);
+    }
+}
+
+impl<T: std::fmt::Debug, D> std::fmt::Debug for CoalescedRanges<T, D> {
+    /// Formats the `[start, limit)` cut pairs as a list in ascending order.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.cuts.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NotNan;
+    use core::ops::Bound::{Excluded, Included, Unbounded};
+    use std::time::Duration;
+
+    fn nn(value: f64) -> NotNan<f64> {
+        NotNan::<f64>::new(value).unwrap()
+    }
+
+    #[test]
+    fn test_cut_ordering() {
+        assert!(Cut::BelowAll < Cut::Below(i8::MIN));
+        assert!(Cut::Below(0) < Cut::Above(0));
+        assert!(Cut::Above(0) < Cut::Below(1));
+        assert!(Cut::Above(i8::MAX) < Cut::AboveAll);
+        assert_eq!(Cut::Above(3).cmp(&Cut::Above(3)), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(i8::normalize(Cut::Above(3)), Cut::Below(4));
+        assert_eq!(i8::normalize(Cut::Below(3)), Cut::Below(3));
+        assert_eq!(i8::normalize(Cut::Below(i8::MIN)), Cut::BelowAll);
+        assert_eq!(i8::normalize(Cut::Above(i8::MAX)), Cut::AboveAll);
+        assert_eq!(i8::normalize(Cut::AboveAll), Cut::AboveAll);
+        assert_eq!(
+            NotNan::<f64>::normalize(Cut::Above(nn(3.0))),
+            Cut::Above(nn(3.0))
+        );
+        assert_eq!(
+            NotNan::<f64>::normalize(Cut::Below(nn(f64::NEG_INFINITY))),
+            Cut::BelowAll
+        );
+        assert_eq!(
+            NotNan::<f64>::normalize(Cut::Above(nn(f64::INFINITY))),
+            Cut::AboveAll
+        );
+        assert_eq!(String::normalize(Cut::Below(String::new())), Cut::BelowAll);
+        assert_eq!(
+            String::normalize(Cut::Above("ab".to_string())),
+            Cut::Below("ab\0".to_string())
+        );
+        assert_eq!(
+            Vec::normalize(Cut::Above(vec![7u8])),
+            Cut::Below(vec![7, 0])
+        );
+        assert_eq!(
+            Duration::normalize(Cut::Above(Duration::from_secs(1))),
+            Cut::Below(Duration::from_nanos(1_000_000_001))
+        );
+    }
+
+    /// Covering everything from the minimum value of a domain covers the whole line.
+    #[test]
+    fn test_domain_minimums() {
+        let mut durations = CoalescedRanges::<Duration>::new();
+        durations.add_range(Duration::ZERO..);
+        assert_eq!(durations.to_range_vec(), [(Unbounded, Unbounded)]);
+        assert!(durations.complement().to_range_vec().is_empty());
+
+        let mut strings = CoalescedRanges::<String>::new();
+        strings.add_range(String::new()..);
+        assert!(strings.complement().to_range_vec().is_empty());
+        // No string lies between `"a"` and `"a\0"`.
+        strings.remove_range((Excluded("a".to_string()), Excluded("a\0".to_string())));
+        assert_eq!(strings.to_range_vec(), [(Unbounded, Unbounded)]);
+
+        let mut floats = CoalescedRanges::<NotNan<f64>>::new();
+        floats.add_range(nn(f64::NEG_INFINITY)..=nn(f64::INFINITY));
+        assert!(floats.complement().to_range_vec().is_empty());
+    }
+
+    /// The inclusive end lets us cover the maximum value of the type.
+    #[test]
+    fn test_inclusive_max() {
+        let mut ranges = CoalescedRanges::<u8>::new();
+        ranges.add_range(250..=255);
+        assert!(ranges.contains(255));
+        assert!(!ranges.contains(249));
+        // No value lies past 255, so the range is unbounded above.
+        assert_eq!(ranges.to_range_vec(), [(Included(250), Unbounded)]);
+        ranges.add_range(0..250);
+        assert_eq!(ranges.to_range_vec(), [(Unbounded, Unbounded)]);
+        ranges.check_invariants();
+    }
+
+    /// Inclusive and exclusive endpoints only coalesce when no value falls between them.
+    #[test]
+    fn test_mixed_bounds_coalescing() {
+        let mut ranges = CoalescedRanges::<NotNan<f64>>::new();
+        ranges.add_range(nn(0.0)..=nn(1.0));
+        ranges.add_range((Excluded(nn(1.0)), Excluded(nn(2.0))));
+        assert_eq!(
+            ranges.to_range_vec(),
+            [(Included(nn(0.0)), Excluded(nn(2.0)))]
+        );
+
+        let mut ranges = CoalescedRanges::<NotNan<f64>>::new();
+        ranges.add_range(nn(0.0)..nn(1.0));
+        ranges.add_range((Excluded(nn(1.0)), Excluded(nn(2.0))));
+        assert_eq!(
+            ranges.to_range_vec(),
+            [
+                (Included(nn(0.0)), Excluded(nn(1.0))),
+                (Excluded(nn(1.0)), Excluded(nn(2.0)))
+            ]
+        );
+        assert!(!ranges.contains(nn(1.0)));
+        assert!(!ranges.contains_partial_range(nn(1.0)..=nn(1.0)));
+        assert!(ranges.contains_partial_range(nn(1.0)..nn(2.0)));
+
+        // Filling in the single missing point coalesces everything.
+        assert_eq!(
+            ranges.add_range(nn(1.0)..=nn(1.0)),
+            [(Included(nn(1.0)), Included(nn(1.0)))]
+        );
+        assert_eq!(
+            ranges.to_range_vec(),
+            [(Included(nn(0.0)), Excluded(nn(2.0)))]
+        );
+        ranges.check_invariants();
+    }
+
+    /// On a discrete domain, ranges with no value between them coalesce, and a range holding no
+    /// values is empty.
+    #[test]
+    fn test_discrete_coalescing() {
+        let mut ranges = CoalescedRanges::<i64>::new();
+        ranges.add_range(0..=1);
+        ranges.add_range(2..=3);
+        assert_eq!(ranges.to_range_vec(), [(Included(0), Excluded(4))]);
+        assert_eq!(ranges.add_range((Excluded(1), Included(2))), []);
+
+        let mut ranges = CoalescedRanges::<i64>::new();
+        assert_eq!(ranges.add_range((Excluded(1), Excluded(2))), []);
+        assert_eq!(ranges.to_range_vec(), []);
+        ranges.add_range(0..1);
+        assert!(!ranges.contains(1));
+        assert!(!ranges.contains_partial_range(1..2));
+        assert!(!ranges.contains_partial_range((Excluded(0), Excluded(1))));
+        assert_eq!(
+            ranges.add_range((Excluded(0), Included(1))),
+            [(Included(1), Excluded(2))]
+        );
+        assert_eq!(ranges.to_range_vec(), [(Included(0), Excluded(2))]);
+        ranges.check_invariants();
+    }
+
+    #[test]
+    fn test_half_unbounded_ranges() {
+        let mut ranges = CoalescedRanges::<i64>::new();
+        ranges.add_range(..0);
+        ranges.add_range(10..);
+        assert_eq!(
+            ranges.to_range_vec(),
+            [(Unbounded, Excluded(0)), (Included(10), Unbounded)]
+        );
+        assert!(ranges.contains(i64::MIN));
+        assert!(ranges.contains(i64::MAX));
+        assert!(!ranges.contains(5));
+        assert_eq!(
+            ranges.get_range_containing(20),
+            Some((Included(10), Unbounded))
+        );
+        assert_eq!(
+            ranges.remove_range(..=10),
+            [(Unbounded, Excluded(0)), (Included(10), Excluded(11))]
+        );
+        assert_eq!(ranges.to_range_vec(), [(Included(11), Unbounded)]);
+        ranges.check_invariants();
+    }
+
+    /// Unbounded ends flow through the core operations like any other cut.
+    #[test]
+    fn test_unbounded_queries() {
+        let mut ranges = CoalescedRanges::<i64>::new();
+        ranges.add_range(1000..);
+        ranges.add_range(..0);
+        assert_eq!(
+            ranges.as_intervals().to_vec(),
+            [
+                (Cut::BelowAll, Cut::Below(0)),
+                (Cut::Below(1000), Cut::AboveAll)
+            ]
+        );
+        assert_eq!(
+            ranges
+                .as_intervals()
+                .get_interval_containing(Cut::Below(i64::MIN)),
+            Some(Interval::new(Cut::BelowAll, Cut::Below(0)))
+        );
+        assert_eq!(
+            ranges.get_first_range_from(0),
+            Some((Included(1000), Unbounded))
+        );
+        assert_eq!(
+            ranges.as_intervals().get_first_start_from(Cut::BelowAll),
+            Some(Interval::new(Cut::BelowAll, Cut::Below(0)))
+        );
+        assert_eq!(ranges.get_first_range_from(1001), None);
+
+        // Adding the middle covers the whole line.
+        assert_eq!(ranges.add_range(-5..=1000), [(Included(0), Excluded(1000))]);
+        assert_eq!(ranges.to_range_vec(), [(Unbounded, Unbounded)]);
+        assert!(ranges.complement().to_range_vec().is_empty());
+        ranges.check_invariants();
+    }
+
+    #[test]
+    fn test_complement() {
+        let empty = CoalescedRanges::<i64>::new();
+        assert_eq!(empty.complement().to_range_vec(), [(Unbounded, Unbounded)]);
+
+        let mut ranges = CoalescedRanges::<i64>::new();
+        ranges.add_range(0..10);
+        ranges.add_range(20..=30);
+        let complement = ranges.complement();
+        assert_eq!(
+            complement.to_range_vec(),
+            [
+                (Unbounded, Excluded(0)),
+                (Included(10), Excluded(20)),
+                (Included(31), Unbounded)
+            ]
+        );
+        complement.check_invariants();
+        assert_eq!(complement.complement(), ranges);
+        assert_eq!(
+            ranges.union(&complement).to_range_vec(),
+            [(Unbounded, Unbounded)]
+        );
+        assert!(ranges.intersection(&complement).to_range_vec().is_empty());
+
+        let mut everything = CoalescedRanges::<i64>::new();
+        everything.add_range(..);
+        assert!(everything.complement().to_range_vec().is_empty());
+    }
+
+    /// On a discrete domain, covering every value covers the whole line, so no run holding no
+    /// values is left past either end.
+    #[test]
+    fn test_whole_discrete_domain() {
+        let mut bytes = CoalescedRanges::<u8>::new();
+        bytes.add_range(0..=255);
+        assert_eq!(bytes.to_range_vec(), [(Unbounded, Unbounded)]);
+        assert!(bytes.complement().to_range_vec().is_empty());
+
+        let mut bytes = CoalescedRanges::<u8>::new();
+        bytes.add_range(..10);
+        assert_eq!(bytes.remove_range(0..10), [(Unbounded, Excluded(10))]);
+        assert!(bytes.to_range_vec().is_empty());
+
+        let mut bytes = CoalescedRanges::<u8>::new();
+        bytes.add_range(1..255);
+        assert_eq!(
+            bytes.complement().to_range_vec(),
+            [(Unbounded, Excluded(1)), (Included(255), Unbounded)]
+        );
+        bytes.check_invariants();
+    }
+
+    /// Ranges whose ends have equal values are empty rather than inverted.
+    #[test]
+    fn test_empty_ranges() {
+        let mut ranges = CoalescedRanges::<i64>::new();
+        assert_eq!(ranges.add_range((Excluded(1), Excluded(1))), []);
+        assert_eq!(ranges.add_range(1..1), []);
+        assert_eq!(ranges.to_range_vec(), []);
+        ranges.add_range(0..5);
+        assert!(ranges.contains_partial_range(1..1));
+        assert!(!ranges.contains_partial_range(5..5));
+    }
+
+    #[test]
+    fn test_try_ranges() {
+        let mut ranges = CoalescedRanges::<i64>::new();
+        let inverted = Err(IntervalError::InvertedBounds { start: 3, limit: 1 });
+        #[allow(clippy::reversed_empty_ranges)]
+        {
+            assert_eq!(ranges.try_add_range(3..1), inverted);
+            assert_eq!(ranges.try_remove_range(3..=1), inverted);
+            assert_eq!(
+                ranges.try_contains_partial_range((Excluded(3), Excluded(1))),
+                inverted.map(|_| false)
+            );
+        }
+        assert_eq!(
+            ranges.try_add_range(1..3),
+            Ok(vec![(Included(1), Excluded(3))])
+        );
+        assert_eq!(ranges.try_contains_partial_range(..2), Ok(true));
+        assert_eq!(
+            ranges.try_remove_range(2..),
+            Ok(vec![(Included(2), Excluded(3))])
+        );
+        assert_eq!(ranges.to_range_vec(), [(Included(1), Excluded(2))]);
+    }
+
+    /// Endpoints only need to be `Ord + Clone`; e.g. lexicographic key spans.
+    #[test]
+    fn test_string_ranges() {
+        let key = String::from;
+        let mut spans = CoalescedRanges::<String>::new();
+        spans.add_range(key("a")..=key("m"));
+        spans.add_range((Excluded(key("m")), Unbounded));
+        assert_eq!(spans.to_range_vec(), [(Included(key("a")), Unbounded)]);
+        assert!(spans.contains(key("zebra")));
+        assert!(!spans.contains(key("")));
+        spans.remove_range(key("b")..key("c"));
+        assert_eq!(
+            spans.complement().to_range_vec(),
+            [
+                (Unbounded, Excluded(key("a"))),
+                (Included(key("b")), Excluded(key("c")))
+            ]
+        );
+        spans.check_invariants();
+    }
+
+    /// Any `Ord` type can use the `Dense` domain, and a domain can be supplied for a type defined
+    /// elsewhere.
+    #[test]
+    fn test_dense_and_custom_domains() {
+        let mut versions = CoalescedRanges::<(u32, u32), Dense>::new();
+        versions.add_range((1, 0)..(2, 0));
+        versions.add_range((2, 0)..=(2, 5));
+        assert_eq!(
+            versions.to_range_vec(),
+            [(Included((1, 0)), Included((2, 5)))]
+        );
+        assert!(versions.contains((1, 99)));
+        versions.check_invariants();
+
+        /// Versions that step by minor number within each major version.
+        struct Minor;
+
+        impl Domain<(u32, u32)> for Minor {
+            fn normalize(cut: Cut<(u32, u32)>) -> Cut<(u32, u32)> {
+                match cut {
+                    Cut::Above((major, minor)) if minor < u32::MAX => {
+                        Cut::Below((major, minor + 1))
+                    }
+                    cut => cut,
+                }
+            }
+        }
+
+        let mut versions = CoalescedRanges::<(u32, u32), Minor>::new();
+        versions.add_range((1, 0)..=(1, 4));
+        versions.add_range((1, 5)..=(1, 9));
+        assert_eq!(
+            versions.to_range_vec(),
+            [(Included((1, 0)), Excluded((1, 10)))]
+        );
+        versions.check_invariants();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_inverted_range() {
+        let mut ranges = CoalescedRanges::<i64>::new();
+        #[allow(clippy::reversed_empty_ranges)]
+        ranges.add_range(3..1);
+    }
+}
@@ -0,0 +1,337 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+use core::ops::Bound;
+
+use crate::{CoalescedRanges, IntervalError};
+
+/// Types with a discrete domain, where every value has a well-defined successor and predecessor.
+///
+/// This is what lets [`DiscreteIntervals`] coalesce inclusive ranges that are adjacent-by-one,
+/// like `[0, 1]` and `[2, 3]`.
+pub trait Step: Copy + Ord {
+    /// The minimum value.
+    const MIN: Self;
+
+    /// The maximum value.
+    const MAX: Self;
+
+    /// Returns the next value, or `None` if `self` is the maximum value.
+    fn successor(&self) -> Option<Self>;
+
+    /// Returns the previous value, or `None` if `self` is the minimum value.
+    fn predecessor(&self) -> Option<Self>;
+}
+
+macro_rules! impl_step_for_integer {
+    ($($t:ty),*) => {
+        $(
+            impl Step for $t {
+                const MIN: Self = <$t>::MIN;
+                const MAX: Self = <$t>::MAX;
+
+                fn successor(&self) -> Option<Self> {
+                    self.checked_add(1)
+                }
+
+                fn predecessor(&self) -> Option<Self> {
+                    self.checked_sub(1)
+                }
+            }
+        )*
+    };
+}
+
+impl_step_for_integer!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl Step for char {
+    const MIN: Self = '\0';
+    const MAX: Self = char::MAX;
+
+    /// Steps over the surrogate range, which holds no `char` values.
+    fn successor(&self) -> Option<Self> {
+        match *self {
+            '\u{D7FF}' => Some('\u{E000}'),
+            c => char::from_u32(c as u32 + 1),
+        }
+    }
+
+    /// Steps over the surrogate range, which holds no `char` values.
+    fn predecessor(&self) -> Option<Self> {
+        match *self {
+            '\u{E000}' => Some('\u{D7FF}'),
+            c => (c as u32).checked_sub(1).and_then(char::from_u32),
+        }
+    }
+}
+
+impl Step for Ipv4Addr {
+    const MIN: Self = Ipv4Addr::UNSPECIFIED;
+    const MAX: Self = Ipv4Addr::BROADCAST;
+
+    fn successor(&self) -> Option<Self> {
+        u32::from(*self).successor().map(Ipv4Addr::from)
+    }
+
+    fn predecessor(&self) -> Option<Self> {
+        u32::from(*self).predecessor().map(Ipv4Addr::from)
+    }
+}
+
+impl Step for Ipv6Addr {
+    const MIN: Self = Ipv6Addr::UNSPECIFIED;
+    const MAX: Self = Ipv6Addr::new(
+        0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff,
+    );
+
+    fn successor(&self) -> Option<Self> {
+        u128::from(*self).successor().map(Ipv6Addr::from)
+    }
+
+    fn predecessor(&self) -> Option<Self> {
+        u128::from(*self).predecessor().map(Ipv6Addr::from)
+    }
+}
+
+/// Steps by one nanosecond, the resolution of `Duration`.
+impl Step for Duration {
+    const MIN: Self = Duration::ZERO;
+    const MAX: Self = Duration::MAX;
+
+    fn successor(&self) -> Option<Self> {
+        self.checked_add(Duration::from_nanos(1))
+    }
+
+    fn predecessor(&self) -> Option<Self> {
+        self.checked_sub(Duration::from_nanos(1))
+    }
+}
+
+/// Maximally coalesced set of inclusive `[first, last]` intervals over a discrete domain.
+///
+/// Since there's no value between `1` and `2` for integers, adding `[0, 1]` and then `[2, 3]`
+/// results in the single interval `[0, 3]`. Single values can be added with
+/// [`add_point`](Self::add_point), and the maximum value of the type can be covered.
+///
+/// Implementation note: the intervals are held as [`CoalescedRanges`] over the domain of `T`,
+/// whose cuts are normalized so that the limit of `[first, last]` sits just below the successor of
+/// `last` (e.g. `[0, 1]` is held as `[Below(0), Below(2))`); intervals that are adjacent-by-one
+/// then share a cut and coalesce.
+pub struct DiscreteIntervals<T> {
+    ranges: CoalescedRanges<T>,
+}
+
+impl<T: Step> Default for DiscreteIntervals<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Step + std::fmt::Debug> DiscreteIntervals<T> {
+    /// Checks interval invariants for this data structure -- panics if there are internal
+    /// inconsistencies.
+    pub fn check_invariants(&self) {
+        self.ranges.check_invariants();
+    }
+}
+
+impl<T: Step> DiscreteIntervals<T> {
+    /// Creates a new (empty) set of discrete intervals.
+    pub fn new() -> Self {
+        DiscreteIntervals {
+            ranges: CoalescedRanges::new(),
+        }
+    }
+
+    /// Returns the `[first, last]` values held between the normalized bounds; an unbounded end
+    /// stands for the minimum or maximum value.
+    fn from_bounds((start, end): (Bound<T>, Bound<T>)) -> RangeInclusive<T> {
+        let first = match start {
+            Bound::Included(first) => first,
+            Bound::Unbounded => T::MIN,
+            Bound::Excluded(_) => panic!("Attempted to convert a start that was not normalized"),
+        };
+        let last = match end {
+            Bound::Excluded(next) => next
+                .predecessor()
+                .expect("normalized limit should follow a value"),
+            Bound::Unbounded => T::MAX,
+            Bound::Included(_) => panic!("Attempted to convert a limit that was not normalized"),
+        };
+        first..=last
+    }
+
+    /// Adds the inclusive interval `[first, last]` to the current interval set.
+    ///
+    /// Returns the inclusive sub-ranges that were not covered before this call, in ascending
+    /// order.
+    pub fn add(&mut self, first: T, last: T) -> Vec<RangeInclusive<T>> {
+        self.ranges
+            .add_range(first..=last)
+            .into_iter()
+            .map(Self::from_bounds)
+            .collect()
+    }
+
+    /// Fallible form of [`add`](Self::add): returns [`IntervalError::InvertedBounds`] instead of
+    /// panicking when `first > last`.
+    pub fn try_add(
+        &mut self,
+        first: T,
+        last: T,
+    ) -> Result<Vec<RangeInclusive<T>>, IntervalError<T>> {
+        IntervalError::check_bounds(&first, &last)?;
+        Ok(self.add(first, last))
+    }
+
+    /// Adds the single value `value` to the current interval set, returning whether it was newly
+    /// added.
+    pub fn add_point(&mut self, value: T) -> bool {
+        !self.add(value, value).is_empty()
+    }
+
+    /// Removes the inclusive interval `[first, last]` from the current interval set.
+    ///
+    /// Returns the inclusive sub-ranges that were actually removed, in ascending order.
+    pub fn remove(&mut self, first: T, last: T) -> Vec<RangeInclusive<T>> {
+        self.ranges
+            .remove_range(first..=last)
+            .into_iter()
+            .map(Self::from_bounds)
+            .collect()
+    }
+
+    /// Fallible form of [`remove`](Self::remove): returns [`IntervalError::InvertedBounds`]
+    /// instead of panicking when `first > last`.
+    pub fn try_remove(
+        &mut self,
+        first: T,
+        last: T,
+    ) -> Result<Vec<RangeInclusive<T>>, IntervalError<T>> {
+        IntervalError::check_bounds(&first, &last)?;
+        Ok(self.remove(first, last))
+    }
+
+    /// Removes the single value `value` from the current interval set, returning whether it was
+    /// present.
+    pub fn remove_point(&mut self, value: T) -> bool {
+        !self.remove(value, value).is_empty()
+    }
+
+    /// Returns whether `value` is in the current interval set.
+    pub fn contains(&self, value: T) -> bool {
+        self.ranges.contains(value)
+    }
+
+    /// Returns the inclusive interval that contains `value`, or `None` if there is none.
+    pub fn get_interval_containing(&self, value: T) -> Option<RangeInclusive<T>> {
+        self.ranges
+            .get_range_containing(value)
+            .map(Self::from_bounds)
+    }
+
+    /// Returns an iterator over the inclusive `[first, last]` intervals in sorted (ascending)
+    /// order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = RangeInclusive<T>> + '_ {
+        self.ranges.iter_ranges().map(Self::from_bounds)
+    }
+
+    /// Converts the current interval set to a vector of inclusive `[first, last]` intervals in
+    /// sorted (ascending) order.
+    pub fn to_vec(&self) -> Vec<RangeInclusive<T>> {
+        self.iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Inclusive intervals that are adjacent-by-one coalesce.
+    #[test]
+    fn test_adjacent_by_one() {
+        let mut ivals = DiscreteIntervals::<i64>::new();
+        assert_eq!(ivals.add(0, 1), [0..=1]);
+        assert_eq!(ivals.add(3, 4), [3..=4]);
+        assert_eq!(ivals.to_vec(), [0..=1, 3..=4]);
+        assert_eq!(ivals.add(2, 2), [2..=2]);
+        assert_eq!(ivals.to_vec(), [0..=4]);
+        assert_eq!(ivals.get_interval_containing(4), Some(0..=4));
+        assert_eq!(ivals.get_interval_containing(5), None);
+        ivals.check_invariants();
+
+        assert_eq!(ivals.remove(2, 3), [2..=3]);
+        assert_eq!(ivals.to_vec(), [0..=1, 4..=4]);
+        ivals.check_invariants();
+    }
+
+    #[test]
+    fn test_points() {
+        let mut ivals = DiscreteIntervals::<u8>::new();
+        assert!(ivals.add_point(255));
+        assert!(ivals.add_point(254));
+        assert!(!ivals.add_point(255));
+        assert!(ivals.add_point(0));
+        assert_eq!(ivals.to_vec(), [0..=0, 254..=255]);
+        ivals.add(1, 253);
+        assert_eq!(ivals.to_vec(), [0..=255]);
+        assert!(ivals.remove_point(255));
+        assert!(!ivals.contains(255));
+        assert_eq!(ivals.to_vec(), [0..=254]);
+        ivals.check_invariants();
+    }
+
+    #[test]
+    fn test_try_forms() {
+        let mut ivals = DiscreteIntervals::<i64>::new();
+        let inverted = Err(IntervalError::InvertedBounds { start: 3, limit: 2 });
+        assert_eq!(ivals.try_add(3, 2), inverted);
+        assert_eq!(ivals.try_remove(3, 2), inverted);
+        assert_eq!(ivals.try_add(2, 3), Ok(vec![2..=3]));
+        assert_eq!(ivals.try_remove(3, 3), Ok(vec![3..=3]));
+        assert_eq!(ivals.to_vec(), [2..=2]);
+    }
+
+    /// `char` steps over the surrogate range.
+    #[test]
+    fn test_char() {
+        assert_eq!('a'.successor(), Some('b'));
+        assert_eq!(char::MAX.successor(), None);
+        assert_eq!('\0'.predecessor(), None);
+
+        let mut ivals = DiscreteIntervals::<char>::new();
+        ivals.add('a', 'c');
+        ivals.add_point('d');
+        ivals.add('\u{D000}', '\u{D7FF}');
+        ivals.add('\u{E000}', '\u{E0FF}');
+        assert_eq!(ivals.to_vec(), ['a'..='d', '\u{D000}'..='\u{E0FF}']);
+    }
+
+    #[test]
+    fn test_ip_addrs() {
+        let mut ivals = DiscreteIntervals::<Ipv4Addr>::new();
+        ivals.add(Ipv4Addr::new(10, 0, 0, 0), Ipv4Addr::new(10, 0, 0, 255));
+        ivals.add(Ipv4Addr::new(10, 0, 1, 0), Ipv4Addr::new(10, 0, 1, 255));
+        assert_eq!(
+            ivals.to_vec(),
+            [Ipv4Addr::new(10, 0, 0, 0)..=Ipv4Addr::new(10, 0, 1, 255)]
+        );
+        ivals.add_point(Ipv4Addr::BROADCAST);
+        assert!(ivals.contains(Ipv4Addr::BROADCAST));
+
+        let mut ivals = DiscreteIntervals::<Ipv6Addr>::new();
+        ivals.add_point(Ipv6Addr::LOCALHOST);
+        ivals.add_point(Ipv6Addr::UNSPECIFIED);
+        assert_eq!(
+            ivals.to_vec(),
+            [Ipv6Addr::UNSPECIFIED..=Ipv6Addr::LOCALHOST]
+        );
+        ivals.add_point(Ipv6Addr::MAX);
+        assert_eq!(
+            ivals.get_interval_containing(Ipv6Addr::MAX),
+            Some(Ipv6Addr::MAX..=Ipv6Addr::MAX)
+        );
+        ivals.check_invariants();
+    }
+}
@@ -0,0 +1,168 @@
+use crate::Interval;
+
+/// Errors reported by the non-panicking (`try_*`) forms of the interval APIs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntervalError<T> {
+    /// The given interval had `start > limit`.
+    InvertedBounds { start: T, limit: T },
+    /// Coverage was removed from `[start, limit)` but that range was not covered; see
+    /// [`CoalescedCoverage::try_remove`](crate::CoalescedCoverage::try_remove).
+    NotCovered { start: T, limit: T },
+    /// Converting between inclusive and half-open forms needed to step past `value`, the
+    /// maximum (or minimum) value of its type; see [`Interval`](crate::Interval).
+    Overflow { value: T },
+}
+
+impl<T: std::cmp::Ord + Clone> IntervalError<T> {
+    /// Returns `InvertedBounds` if `[start, limit)` is not a well-formed interval.
+    pub(crate) fn check_bounds(start: &T, limit: &T) -> Result<(), Self> {
+        if start <= limit {
+            Ok(())
+        } else {
+            Err(IntervalError::InvertedBounds {
+                start: start.clone(),
+                limit: limit.clone(),
+            })
+        }
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Display for IntervalError<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IntervalError::InvertedBounds { start, limit } => {
+                write!(
+                    f,
+                    "inverted interval bounds: start {:?} > limit {:?}",
+                    start, limit
+                )
+            }
+            IntervalError::NotCovered { start, limit } => {
+                write!(f, "interval [{:?}, {:?}) is not covered", start, limit)
+            }
+            IntervalError::Overflow { value } => {
+                write!(f, "cannot step past {:?}", value)
+            }
+        }
+    }
+}
+
+impl<T: std::fmt::Debug> std::error::Error for IntervalError<T> {}
+
+/// Describes an internal inconsistency found by
+/// [`CoalescedIntervals::validate`](crate::CoalescedIntervals::validate), naming the offending
+/// interval(s).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvariantViolation<T> {
+    /// An interval with `start == limit` is being held.
+    Empty { start: T, limit: T },
+    /// An interval with `start > limit` is being held.
+    Inverted { start: T, limit: T },
+    /// The interval is present in one of the two underlying mappings but not mirrored in the
+    /// other.
+    Asymmetric { start: T, limit: T },
+    /// Two neighboring intervals overlap.
+    Overlapping {
+        first: Interval<T>,
+        second: Interval<T>,
+    },
+    /// Two neighboring intervals abut, so they should have been coalesced.
+    Abutting {
+        first: Interval<T>,
+        second: Interval<T>,
+    },
+}
+
+impl<T> InvariantViolation<T> {
+    /// Describes the kind of violation without naming the offending values, for when `T` is
+    /// not `Debug`.
+    #[cfg(feature = "paranoid")]
+    pub(crate) fn reason(&self) -> &'static str {
+        match self {
+            InvariantViolation::Empty { .. } => "empty interval",
+            InvariantViolation::Inverted { .. } => "inverted interval",
+            InvariantViolation::Asymmetric { .. } => "interval is not mirrored in both mappings",
+            InvariantViolation::Overlapping { .. } => "intervals overlap",
+            InvariantViolation::Abutting { .. } => "intervals abut but were not coalesced",
+        }
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Display for InvariantViolation<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvariantViolation::Empty { start, limit } => {
+                write!(f, "empty interval [{:?}, {:?})", start, limit)
+            }
+            InvariantViolation::Inverted { start, limit } => {
+                write!(f, "inverted interval [{:?}, {:?})", start, limit)
+            }
+            InvariantViolation::Asymmetric { start, limit } => write!(
+                f,
+                "interval [{:?}, {:?}) is not mirrored in both mappings",
+                start, limit
+            ),
+            InvariantViolation::Overlapping { first, second } => write!(
+                f,
+                "intervals [{:?}, {:?}) and [{:?}, {:?}) overlap",
+                first.start(),
+                first.limit(),
+                second.start(),
+                second.limit()
+            ),
+            InvariantViolation::Abutting { first, second } => write!(
+                f,
+                "intervals [{:?}, {:?}) and [{:?}, {:?}) abut but were not coalesced",
+                first.start(),
+                first.limit(),
+                second.start(),
+                second.limit()
+            ),
+        }
+    }
+}
+
+impl<T: std::fmt::Debug> std::error::Error for InvariantViolation<T> {}
+
+/// Error returned when parsing a [`CoalescedIntervals`](crate::CoalescedIntervals) from its
+/// textual form fails; see [`from_str_strict`](crate::CoalescedIntervals::from_str_strict).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// Byte offset into the input at which the problem was found.
+    pub position: usize,
+    pub reason: ParseErrorReason,
+}
+
+/// Why parsing an interval set failed; see [`ParseError`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorReason {
+    /// The given token (e.g. `'['`) was expected but something else was found.
+    Expected(&'static str),
+    /// An endpoint could not be parsed; holds the endpoint parser's message.
+    InvalidEndpoint(String),
+    /// An interval had `start > limit`.
+    InvertedBounds,
+    /// In strict mode, an interval overlapped one given earlier.
+    Overlapping,
+    /// There was more input after the closing `'}'`.
+    TrailingInput,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid interval set at position {}: ", self.position)?;
+        match &self.reason {
+            ParseErrorReason::Expected(token) => write!(f, "expected {}", token),
+            ParseErrorReason::InvalidEndpoint(message) => {
+                write!(f, "invalid endpoint: {}", message)
+            }
+            ParseErrorReason::InvertedBounds => write!(f, "inverted interval bounds"),
+            ParseErrorReason::Overlapping => {
+                write!(f, "interval overlaps an earlier interval")
+            }
+            ParseErrorReason::TrailingInput => write!(f, "unexpected input after '}}'"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
@@ -0,0 +1,234 @@
+use std::cmp::Ordering;
+
+use crate::{CoalescedIntervals, Cut, Domain, Interval};
+
+/// Error returned when attempting to make a [`NotNan`] from a NaN value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NanError;
+
+impl std::fmt::Display for NanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "NaN is not a valid interval endpoint")
+    }
+}
+
+impl std::error::Error for NanError {}
+
+/// Floating point value that is known not to be NaN, which gives it the total order needed to
+/// be used as a [`CoalescedIntervals`] endpoint.
+///
+/// Infinities are allowed, and negative zero is normalized to positive zero so that the two
+/// compare (and hash) as the same endpoint.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NotNan<F>(F);
+
+macro_rules! impl_not_nan {
+    ($($f:ty),*) => {
+        $(
+            impl NotNan<$f> {
+                /// Wraps `value`, returning an error if it is NaN.
+                pub fn new(value: $f) -> Result<Self, NanError> {
+                    if value.is_nan() {
+                        Err(NanError)
+                    } else if value == 0.0 {
+                        Ok(NotNan(0.0))
+                    } else {
+                        Ok(NotNan(value))
+                    }
+                }
+
+                /// Returns the wrapped value.
+                pub fn get(self) -> $f {
+                    self.0
+                }
+            }
+
+            impl Eq for NotNan<$f> {}
+
+            impl Ord for NotNan<$f> {
+                fn cmp(&self, other: &Self) -> Ordering {
+                    self.0
+                        .partial_cmp(&other.0)
+                        .expect("NotNan should never hold NaN")
+                }
+            }
+
+            impl PartialOrd for NotNan<$f> {
+                fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                    Some(self.cmp(other))
+                }
+            }
+
+            impl std::hash::Hash for NotNan<$f> {
+                fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                    self.0.to_bits().hash(state);
+                }
+            }
+
+            impl TryFrom<$f> for NotNan<$f> {
+                type Error = NanError;
+
+                fn try_from(value: $f) -> Result<Self, NanError> {
+                    NotNan::<$f>::new(value)
+                }
+            }
+
+            impl From<NotNan<$f>> for $f {
+                fn from(value: NotNan<$f>) -> $f {
+                    value.0
+                }
+            }
+
+            /// Normalizes the cuts beyond the infinities, but is otherwise dense; i.e. a float
+            /// and the next representable one are not treated as adjacent.
+            impl Domain<NotNan<$f>> for NotNan<$f> {
+                fn normalize(cut: Cut<NotNan<$f>>) -> Cut<NotNan<$f>> {
+                    match cut {
+                        Cut::Below(value) if value.0 == <$f>::NEG_INFINITY => Cut::BelowAll,
+                        Cut::Above(value) if value.0 == <$f>::INFINITY => Cut::AboveAll,
+                        cut => cut,
+                    }
+                }
+            }
+
+            impl CoalescedIntervals<NotNan<$f>> {
+                /// Adds the interval `[start, limit)` given as raw floats, returning an error
+                /// (and leaving the set untouched) if either endpoint is NaN.
+                ///
+                /// Returns the sub-ranges that were not covered before this call, as with
+                /// [`add`](Self::add).
+                pub fn try_add_float(
+                    &mut self,
+                    start: $f,
+                    limit: $f,
+                ) -> Result<Vec<Interval<NotNan<$f>>>, NanError> {
+                    let start = NotNan::<$f>::new(start)?;
+                    let limit = NotNan::<$f>::new(limit)?;
+                    Ok(self.add(start, limit))
+                }
+
+                /// Adds the interval `[start, limit)` and then also coalesces the resulting
+                /// interval with any neighbor that is less than `epsilon` away from it, to absorb
+                /// rounding noise.
+                ///
+                /// The holes bridged this way are covered as though they had been added, and are
+                /// included in the returned sub-ranges that were not covered before this call, as
+                /// with [`add`](Self::add). A zero (or negative) `epsilon` bridges nothing, and
+                /// neither does an empty interval. Note that only the neighborhood of the new
+                /// interval is examined, so a set should consistently be built with the same
+                /// `epsilon`.
+                pub fn add_with_epsilon(
+                    &mut self,
+                    start: NotNan<$f>,
+                    limit: NotNan<$f>,
+                    epsilon: NotNan<$f>,
+                ) -> Vec<Interval<NotNan<$f>>> {
+                    let mut added = self.add(start, limit);
+                    if start == limit {
+                        // An empty interval adds nothing, so there is no new interval whose
+                        // neighbors could be bridged.
+                        return added;
+                    }
+                    let (merged_start, merged_limit) = self
+                        .get_interval_containing(start)
+                        .expect("non-empty interval was just added")
+                        .into();
+                    if let Some(prev) = self.get_first_limit_before(merged_start) {
+                        if merged_start.0 - prev.limit().0 < epsilon.0 {
+                            added.extend(self.add(*prev.limit(), merged_start));
+                        }
+                    }
+                    if let Some(next) = self.get_first_start_from(merged_limit) {
+                        if next.start().0 - merged_limit.0 < epsilon.0 {
+                            added.extend(self.add(merged_limit, *next.start()));
+                        }
+                    }
+                    // Coalesce the bridged holes with the sub-ranges they abut, in ascending
+                    // order.
+                    added.into_iter().collect::<Self>().into_iter().collect()
+                }
+            }
+        )*
+    };
+}
+
+impl_not_nan!(f32, f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nn(value: f64) -> NotNan<f64> {
+        NotNan::<f64>::new(value).unwrap()
+    }
+
+    #[test]
+    fn test_not_nan() {
+        assert_eq!(NotNan::<f64>::new(f64::NAN), Err(NanError));
+        assert_eq!(NotNan::<f32>::try_from(f32::NAN), Err(NanError));
+        assert_eq!(nn(-0.0), nn(0.0));
+        assert_eq!(nn(-0.0).get().to_bits(), 0.0f64.to_bits());
+        assert!(nn(f64::NEG_INFINITY) < nn(-1.5));
+        assert!(nn(1.5) < nn(f64::INFINITY));
+        assert_eq!(f64::from(nn(2.5)), 2.5);
+    }
+
+    #[test]
+    fn test_float_intervals() {
+        let mut ivals = CoalescedIntervals::<NotNan<f64>>::new();
+        assert_eq!(ivals.try_add_float(0.5, f64::NAN), Err(NanError));
+        assert_eq!(
+            ivals.try_add_float(0.5, 1.25),
+            Ok(vec![Interval::new(nn(0.5), nn(1.25))])
+        );
+        ivals.try_add_float(1.25, 2.0).unwrap();
+        assert_eq!(ivals.to_vec(), [(nn(0.5), nn(2.0))]);
+        assert!(ivals.contains_partial(nn(1.9), nn(3.0)));
+        ivals.check_invariants();
+    }
+
+    /// Intervals closer than epsilon coalesce; those further apart do not.
+    #[test]
+    fn test_add_with_epsilon() {
+        let mut ivals = CoalescedIntervals::<NotNan<f64>>::new();
+        let epsilon = nn(1e-9);
+        // `0.1 + 0.2` lands just past `0.3`, leaving a sliver of a hole.
+        ivals.add_with_epsilon(nn(0.0), nn(0.3), epsilon);
+        assert_eq!(
+            ivals.add_with_epsilon(nn(0.1 + 0.2), nn(1.0), epsilon),
+            [(nn(0.3), nn(1.0))]
+        );
+        assert_eq!(ivals.to_vec(), [(nn(0.0), nn(1.0))]);
+
+        assert_eq!(
+            ivals.add_with_epsilon(nn(1.5), nn(2.0), epsilon),
+            [(nn(1.5), nn(2.0))]
+        );
+        assert_eq!(ivals.to_vec(), [(nn(0.0), nn(1.0)), (nn(1.5), nn(2.0))]);
+
+        // Bridging on both sides at once.
+        assert_eq!(
+            ivals.add_with_epsilon(nn(1.0 + 1e-12), nn(1.5 - 1e-12), epsilon),
+            [(nn(1.0), nn(1.5))]
+        );
+        assert_eq!(ivals.to_vec(), [(nn(0.0), nn(2.0))]);
+        assert!(ivals.add_with_epsilon(nn(0.5), nn(0.5), epsilon).is_empty());
+        ivals.check_invariants();
+
+        // An empty interval bridges nothing, even inside a hole narrower than epsilon.
+        let mut ivals = CoalescedIntervals::<NotNan<f64>>::new();
+        ivals.add(nn(0.0), nn(1.0));
+        ivals.add(nn(1.0 + 1e-12), nn(2.0));
+        assert!(ivals.add_with_epsilon(nn(0.5), nn(0.5), epsilon).is_empty());
+        assert!(ivals
+            .add_with_epsilon(nn(1.0 + 1e-13), nn(1.0 + 1e-13), epsilon)
+            .is_empty());
+        assert_eq!(ivals.to_vec().len(), 2);
+
+        // A negative epsilon bridges nothing.
+        let mut ivals = CoalescedIntervals::<NotNan<f64>>::new();
+        ivals.add(nn(0.0), nn(0.3));
+        ivals.add_with_epsilon(nn(0.1 + 0.2), nn(1.0), nn(-1.0));
+        assert_eq!(ivals.to_vec().len(), 2);
+    }
+}
@@ -0,0 +1,624 @@
+use core::ops::Bound;
+
+use crate::{CoalescedIntervals, Interval, IntervalError, Measure};
+
+/// Interval count and covered length of a group of intervals.
+struct Summary<L> {
+    count: usize,
+    len: L,
+}
+
+impl<L: Copy + Default + std::ops::Add<Output = L>> Summary<L> {
+    fn zero() -> Self {
+        Summary {
+            count: 0,
+            len: L::default(),
+        }
+    }
+
+    fn add(self, other: Summary<L>) -> Self {
+        Summary {
+            count: self.count + other.count,
+            len: self.len + other.len,
+        }
+    }
+}
+
+type Link<T> = Option<Box<Node<T>>>;
+
+/// Treap node for the interval `[start, limit)`, keyed by `start` and heap-ordered by
+/// `priority`, which also holds the summary of its whole subtree.
+#[derive(Clone)]
+struct Node<T: Measure> {
+    start: T,
+    limit: T,
+    priority: u64,
+    count: usize,
+    len: T::Length,
+    left: Link<T>,
+    right: Link<T>,
+}
+
+impl<T: Measure> Node<T> {
+    fn own(&self) -> Summary<T::Length> {
+        Summary {
+            count: 1,
+            len: T::distance(&self.start, &self.limit),
+        }
+    }
+
+    /// Recomputes the subtree summary after a child changed.
+    fn update(&mut self) {
+        let summary = subtree(&self.left)
+            .add(self.own())
+            .add(subtree(&self.right));
+        self.count = summary.count;
+        self.len = summary.len;
+    }
+}
+
+fn subtree<T: Measure>(link: &Link<T>) -> Summary<T::Length> {
+    match link {
+        Some(node) => Summary {
+            count: node.count,
+            len: node.len,
+        },
+        None => Summary::zero(),
+    }
+}
+
+/// Splits the subtree into the nodes whose start satisfies `goes_left` and the rest; the
+/// predicate must be monotone over the starts (true for a prefix of them).
+fn split<T: Measure>(link: Link<T>, goes_left: &dyn Fn(&T) -> bool) -> (Link<T>, Link<T>) {
+    match link {
+        None => (None, None),
+        Some(mut node) => {
+            if goes_left(&node.start) {
+                let (middle, right) = split(node.right.take(), goes_left);
+                node.right = middle;
+                node.update();
+                (Some(node), right)
+            } else {
+                let (left, middle) = split(node.left.take(), goes_left);
+                node.left = middle;
+                node.update();
+                (left, Some(node))
+            }
+        }
+    }
+}
+
+/// Joins two subtrees where every start in `left` is less than every start in `right`.
+fn merge<T: Measure>(left: Link<T>, right: Link<T>) -> Link<T> {
+    match (left, right) {
+        (None, right) => right,
+        (left, None) => left,
+        (Some(mut left), Some(mut right)) => {
+            if left.priority > right.priority {
+                left.right = merge(left.right.take(), Some(right));
+                left.update();
+                Some(left)
+            } else {
+                right.left = merge(Some(left), right.left.take());
+                right.update();
+                Some(right)
+            }
+        }
+    }
+}
+
+/// Order-statistics treap over the intervals of a set, keyed by start, that answers interval
+/// count and covered length queries over a range of starts in O(log n).
+#[derive(Clone)]
+struct SummaryTree<T: Measure> {
+    root: Link<T>,
+    /// State of the xorshift generator for node priorities.
+    seed: u64,
+}
+
+impl<T: Ord + Measure> SummaryTree<T> {
+    fn new() -> Self {
+        SummaryTree {
+            root: None,
+            seed: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    fn next_priority(&mut self) -> u64 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        self.seed
+    }
+
+    fn insert(&mut self, start: T, limit: T) {
+        let mut node = Box::new(Node {
+            start,
+            limit,
+            priority: self.next_priority(),
+            count: 0,
+            len: T::Length::default(),
+            left: None,
+            right: None,
+        });
+        node.update();
+        let (left, right) = split(self.root.take(), &|other| *other < node.start);
+        self.root = merge(merge(left, Some(node)), right);
+    }
+
+    /// Removes the interval that starts at `start` -- panics if there is none.
+    fn remove(&mut self, start: &T) {
+        let (left, rest) = split(self.root.take(), &|other| other < start);
+        let (found, right) = split(rest, &|other| other == start);
+        assert!(
+            found.is_some(),
+            "Attempted to remove start that was not present in tree"
+        );
+        self.root = merge(left, right);
+    }
+
+    fn total(&self) -> Summary<T::Length> {
+        subtree(&self.root)
+    }
+
+    /// Summarizes the intervals whose start is < `hi`.
+    fn summary_below(mut link: &Link<T>, hi: &T) -> Summary<T::Length> {
+        let mut summary = Summary::zero();
+        while let Some(node) = link {
+            if node.start < *hi {
+                summary = summary.add(subtree(&node.left)).add(node.own());
+                link = &node.right;
+            } else {
+                link = &node.left;
+            }
+        }
+        summary
+    }
+
+    /// Summarizes the intervals whose start is >= `lo`.
+    fn summary_from(mut link: &Link<T>, lo: &T) -> Summary<T::Length> {
+        let mut summary = Summary::zero();
+        while let Some(node) = link {
+            if node.start >= *lo {
+                summary = summary.add(node.own()).add(subtree(&node.right));
+                link = &node.left;
+            } else {
+                link = &node.right;
+            }
+        }
+        summary
+    }
+
+    /// Summarizes the intervals whose start is in `[lo, hi)`.
+    fn summary_between(&self, lo: &T, hi: &T) -> Summary<T::Length> {
+        // Walk down to the first node inside the range; the rest of the range then lies in its
+        // left subtree (from `lo`) and right subtree (below `hi`).
+        let mut link = &self.root;
+        while let Some(node) = link {
+            if node.start < *lo {
+                link = &node.right;
+            } else if node.start >= *hi {
+                link = &node.left;
+            } else {
+                return Self::summary_from(&node.left, lo)
+                    .add(node.own())
+                    .add(Self::summary_below(&node.right, hi));
+            }
+        }
+        Summary::zero()
+    }
+
+    /// Returns the node of the `index`th interval in ascending order.
+    fn nth(&self, mut index: usize) -> Option<&Node<T>> {
+        let mut link = &self.root;
+        while let Some(node) = link {
+            let left_count = subtree(&node.left).count;
+            if index < left_count {
+                link = &node.left;
+            } else if index == left_count {
+                return Some(node);
+            } else {
+                index -= left_count + 1;
+                link = &node.right;
+            }
+        }
+        None
+    }
+
+    /// Returns the node of the interval holding the covered point `offset` past the start of the
+    /// first interval (counting only covered values), along with how far past the start of that
+    /// interval the point is.
+    fn nth_covered(&self, mut offset: T::Length) -> Option<(&Node<T>, T::Length)> {
+        let mut link = &self.root;
+        while let Some(node) = link {
+            let left_len = subtree(&node.left).len;
+            if offset < left_len {
+                link = &node.left;
+                continue;
+            }
+            offset = offset - left_len;
+            let own_len = node.own().len;
+            if offset < own_len {
+                return Some((node, offset));
+            }
+            offset = offset - own_len;
+            link = &node.right;
+        }
+        None
+    }
+
+    /// Appends the intervals in ascending order, for validation.
+    fn collect(link: &Link<T>, out: &mut Vec<(T, T)>)
+    where
+        T: Clone,
+    {
+        if let Some(node) = link {
+            Self::collect(&node.left, out);
+            out.push((node.start.clone(), node.limit.clone()));
+            Self::collect(&node.right, out);
+        }
+    }
+}
+
+/// Maximally coalesced interval set that also answers covered-length and interval-count queries
+/// over a window in O(log n); see [`covered_len_in`](Self::covered_len_in) and
+/// [`count_in`](Self::count_in). Positional queries like [`nth`](Self::nth) and
+/// [`nth_covered_point`](Self::nth_covered_point) are O(log n) too.
+///
+/// Implementation note: alongside the [`CoalescedIntervals`] we keep a treap over the intervals
+/// (keyed by start) in which every node holds the interval count and covered length of its
+/// subtree; [`add`](Self::add) and [`remove`](Self::remove) keep the two in step.
+#[derive(Clone)]
+pub struct IndexedIntervals<T: Measure> {
+    intervals: CoalescedIntervals<T>,
+    tree: SummaryTree<T>,
+}
+
+impl<T: Ord + Clone + Measure> Default for IndexedIntervals<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord + Clone + Measure> From<CoalescedIntervals<T>> for IndexedIntervals<T> {
+    fn from(intervals: CoalescedIntervals<T>) -> Self {
+        let mut tree = SummaryTree::new();
+        for (start, limit) in intervals.start_to_limit.iter() {
+            tree.insert(start.clone(), limit.clone());
+        }
+        IndexedIntervals { intervals, tree }
+    }
+}
+
+impl<T: Ord + Clone + Measure + std::fmt::Debug> IndexedIntervals<T> {
+    /// Checks interval invariants for this data structure -- panics if there are internal
+    /// inconsistencies, including between the intervals and the summary tree.
+    pub fn check_invariants(&self) {
+        self.intervals.check_invariants();
+        let mut in_tree = vec![];
+        SummaryTree::collect(&self.tree.root, &mut in_tree);
+        let in_set: Vec<(T, T)> = self.intervals.iter().map(<(T, T)>::from).collect();
+        assert_eq!(
+            in_tree, in_set,
+            "summary tree is out of step with the intervals"
+        );
+        fn check_node<T: Ord + Measure>(link: &Link<T>) {
+            if let Some(node) = link {
+                for child in [&node.left, &node.right].into_iter().flatten() {
+                    assert!(child.priority <= node.priority);
+                }
+                check_node(&node.left);
+                check_node(&node.right);
+                let expected = subtree(&node.left)
+                    .add(node.own())
+                    .add(subtree(&node.right));
+                assert_eq!(node.count, expected.count);
+                // `T::Length` need not be `Debug`, so it can't go through `assert_eq!`.
+                assert!(
+                    node.len == expected.len,
+                    "cached subtree length is out of step with its children"
+                );
+            }
+        }
+        check_node(&self.tree.root);
+    }
+}
+
+impl<T: Ord + Clone + Measure> IndexedIntervals<T> {
+    /// Creates a new (empty) set of indexed intervals.
+    pub fn new() -> Self {
+        IndexedIntervals {
+            intervals: CoalescedIntervals::new(),
+            tree: SummaryTree::new(),
+        }
+    }
+
+    /// Returns the underlying interval set, for the queries that don't need the summary tree.
+    pub fn as_intervals(&self) -> &CoalescedIntervals<T> {
+        &self.intervals
+    }
+
+    /// Adds the interval `[start, limit)` to the current interval set; see
+    /// [`CoalescedIntervals::add`].
+    pub fn add(&mut self, start: T, limit: T) -> Vec<Interval<T>> {
+        assert!(start <= limit);
+        if start == limit {
+            return vec![];
+        }
+        // The new interval absorbs everything it overlaps or abuts.
+        let mut absorbed: Vec<T> = self
+            .intervals
+            .start_to_limit
+            .range(self.intervals.overlapping_range(&start, &limit))
+            .map(|(existing_start, _existing_limit)| existing_start.clone())
+            .collect();
+        if let Some(left_start) = self.intervals.limit_to_start.get(&start) {
+            absorbed.push(left_start.clone());
+        }
+        if self.intervals.start_to_limit.contains_key(&limit) {
+            absorbed.push(limit.clone());
+        }
+
+        let added = self.intervals.add(start.clone(), limit);
+        if added.is_empty() {
+            return added;
+        }
+        for absorbed_start in absorbed.iter() {
+            self.tree.remove(absorbed_start);
+        }
+        let (merged_start, merged_limit) = self
+            .intervals
+            .find_containing(&start)
+            .expect("added interval should be covered");
+        self.tree.insert(merged_start.clone(), merged_limit.clone());
+        added
+    }
+
+    /// Fallible form of [`add`](Self::add): returns [`IntervalError::InvertedBounds`] instead of
+    /// panicking when `start > limit`.
+    pub fn try_add(&mut self, start: T, limit: T) -> Result<Vec<Interval<T>>, IntervalError<T>> {
+        IntervalError::check_bounds(&start, &limit)?;
+        Ok(self.add(start, limit))
+    }
+
+    /// Removes the interval `[start, limit)` from the current interval set; see
+    /// [`CoalescedIntervals::remove`].
+    pub fn remove(&mut self, start: T, limit: T) -> Vec<Interval<T>> {
+        assert!(start <= limit);
+        if start == limit {
+            return vec![];
+        }
+        let trimmed: Vec<Interval<T>> = self
+            .intervals
+            .iter_overlapping(start.clone(), limit.clone())
+            .collect();
+        let removed = self.intervals.remove(start.clone(), limit.clone());
+        for interval in trimmed.iter() {
+            self.tree.remove(interval.start());
+        }
+        // Whatever hung over either edge of the removed region remains.
+        if let Some(first) = trimmed.first() {
+            if *first.start() < start {
+                self.tree.insert(first.start().clone(), start);
+            }
+        }
+        if let Some(last) = trimmed.last() {
+            if *last.limit() > limit {
+                self.tree.insert(limit, last.limit().clone());
+            }
+        }
+        removed
+    }
+
+    /// Fallible form of [`remove`](Self::remove): returns [`IntervalError::InvertedBounds`]
+    /// instead of panicking when `start > limit`.
+    pub fn try_remove(&mut self, start: T, limit: T) -> Result<Vec<Interval<T>>, IntervalError<T>> {
+        IntervalError::check_bounds(&start, &limit)?;
+        Ok(self.remove(start, limit))
+    }
+
+    /// Returns the number of (maximally coalesced) intervals.
+    pub fn len(&self) -> usize {
+        self.tree.total().count
+    }
+
+    /// Returns whether there are no intervals.
+    pub fn is_empty(&self) -> bool {
+        self.tree.root.is_none()
+    }
+
+    /// Returns the total length covered by the current interval set, in O(1).
+    pub fn total_len(&self) -> T::Length {
+        self.tree.total().len
+    }
+
+    /// Returns the length of the window `[start, limit)` that is covered by the current interval
+    /// set, in O(log n); see [`CoalescedIntervals::covered_len_in`].
+    pub fn covered_len_in(&self, start: T, limit: T) -> T::Length {
+        assert!(start <= limit);
+        let mut total = T::Length::default();
+        // An interval starting before the window only counts from the window start.
+        if let Some((existing_start, existing_limit)) = self.intervals.find_containing(&start) {
+            if *existing_start < start {
+                total = T::distance(&start, std::cmp::min(existing_limit, &limit));
+            }
+        }
+        // Intervals starting within the window are covered in full, other than the last one,
+        // which may hang over the window limit.
+        if let Some((last_start, last_limit)) = self
+            .intervals
+            .start_to_limit
+            .range((Bound::Included(&start), Bound::Excluded(&limit)))
+            .next_back()
+        {
+            total = total
+                + self.tree.summary_between(&start, last_start).len
+                + T::distance(last_start, std::cmp::min(last_limit, &limit));
+        }
+        total
+    }
+
+    /// Fallible form of [`covered_len_in`](Self::covered_len_in): returns
+    /// [`IntervalError::InvertedBounds`] instead of panicking when `start > limit`.
+    pub fn try_covered_len_in(&self, start: T, limit: T) -> Result<T::Length, IntervalError<T>> {
+        IntervalError::check_bounds(&start, &limit)?;
+        Ok(self.covered_len_in(start, limit))
+    }
+
+    /// Returns the number of intervals that overlap `[start, limit)`, in O(log n); i.e. the
+    /// number yielded by [`CoalescedIntervals::iter_overlapping`].
+    pub fn count_in(&self, start: T, limit: T) -> usize {
+        assert!(start <= limit);
+        let straddling = match self.intervals.find_containing(&start) {
+            Some(_) if start == limit => return 1,
+            Some((existing_start, _existing_limit)) => *existing_start < start,
+            None => false,
+        };
+        usize::from(straddling) + self.tree.summary_between(&start, &limit).count
+    }
+
+    /// Returns the `index`th interval in sorted (ascending) order, in O(log n).
+    pub fn nth(&self, index: usize) -> Option<Interval<T>> {
+        self.tree
+            .nth(index)
+            .map(|node| Interval::new_unchecked(node.start.clone(), node.limit.clone()))
+    }
+
+    /// Returns the index (in sorted order) of the interval that contains `value`, or `None` if
+    /// `value` is not covered, in O(log n).
+    pub fn index_of(&self, value: T) -> Option<usize> {
+        let (start, _limit) = self.intervals.find_containing(&value)?;
+        Some(SummaryTree::summary_below(&self.tree.root, start).count)
+    }
+
+    /// Returns the `index`th covered point in ascending order, counting only covered values, or
+    /// `None` if fewer than `index + 1` values are covered, in O(log n).
+    ///
+    /// This maps the dense index space `[0, total_len())` onto the covered values; e.g. with the
+    /// interval set `{[0, 2), [10, 12)}` the covered points `0, 1, 10, 11` are at indices
+    /// `0, 1, 2, 3`.
+    pub fn nth_covered_point(&self, index: T::Length) -> Option<T> {
+        self.tree
+            .nth_covered(index)
+            .map(|(node, offset)| T::offset(&node.start, offset))
+    }
+
+    /// Fallible form of [`count_in`](Self::count_in): returns
+    /// [`IntervalError::InvertedBounds`] instead of panicking when `start > limit`.
+    pub fn try_count_in(&self, start: T, limit: T) -> Result<usize, IntervalError<T>> {
+        IntervalError::check_bounds(&start, &limit)?;
+        Ok(self.count_in(start, limit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summaries() {
+        let mut ivals = IndexedIntervals::<i64>::new();
+        assert!(ivals.is_empty());
+        ivals.add(0, 10);
+        ivals.add(20, 25);
+        ivals.add(30, 40);
+        assert_eq!(ivals.len(), 3);
+        assert_eq!(ivals.total_len(), 25);
+        assert_eq!(ivals.covered_len_in(5, 35), 15);
+        assert_eq!(ivals.covered_len_in(2, 8), 6);
+        assert_eq!(ivals.covered_len_in(10, 20), 0);
+        assert_eq!(ivals.count_in(5, 35), 3);
+        assert_eq!(ivals.count_in(10, 20), 0);
+        assert_eq!(ivals.count_in(22, 22), 1);
+        ivals.check_invariants();
+
+        // Coalescing shrinks the count but not the covered length.
+        assert_eq!(ivals.add(10, 20), [(10, 20)]);
+        assert_eq!(ivals.len(), 2);
+        assert_eq!(ivals.total_len(), 35);
+        ivals.check_invariants();
+
+        // Splitting grows it.
+        assert_eq!(ivals.remove(5, 15), [(5, 15)]);
+        assert_eq!(ivals.as_intervals().to_vec(), [(0, 5), (15, 25), (30, 40)]);
+        assert_eq!(ivals.total_len(), 25);
+        assert_eq!(ivals.count_in(0, 40), 3);
+        ivals.check_invariants();
+        assert_eq!(ivals.nth(1), Some(Interval::new(15, 25)));
+        assert_eq!(ivals.index_of(30), Some(2));
+        assert_eq!(ivals.index_of(25), None);
+        assert_eq!(ivals.nth_covered_point(4), Some(4));
+        assert_eq!(ivals.nth_covered_point(5), Some(15));
+        assert_eq!(ivals.nth_covered_point(24), Some(39));
+        assert_eq!(ivals.nth_covered_point(25), None);
+        assert_eq!(
+            ivals.try_count_in(1, 0),
+            Err(IntervalError::InvertedBounds { start: 1, limit: 0 })
+        );
+    }
+
+    /// Checks the O(log n) queries against the linear scans over a pseudo-random workload.
+    #[test]
+    fn test_matches_linear_scan() {
+        let mut ivals = IndexedIntervals::<i32>::new();
+        let mut state = 0x1234_5678u32;
+        let mut next = |bound: u32| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            (state % bound) as i32
+        };
+        for round in 0..500 {
+            let start = next(200);
+            let limit = start + next(20);
+            if round % 3 == 0 {
+                ivals.remove(start, limit);
+            } else {
+                ivals.add(start, limit);
+            }
+            let lo = next(220) - 10;
+            let hi = lo + next(60);
+            let linear = ivals.as_intervals();
+            assert_eq!(ivals.covered_len_in(lo, hi), linear.covered_len_in(lo, hi));
+            assert_eq!(
+                ivals.count_in(lo, hi),
+                linear.iter_overlapping(lo, hi).count()
+            );
+            assert_eq!(ivals.total_len(), linear.total_len());
+            assert_eq!(ivals.len(), linear.iter().len());
+        }
+        ivals.check_invariants();
+
+        // Rank and select agree with scans over the intervals and the covered points.
+        let linear = ivals.as_intervals();
+        for (index, interval) in linear.iter().enumerate() {
+            assert_eq!(ivals.nth(index), Some(interval));
+            assert_eq!(ivals.index_of(*interval.start()), Some(index));
+            assert_eq!(ivals.index_of(interval.limit() - 1), Some(index));
+        }
+        assert_eq!(ivals.nth(ivals.len()), None);
+        let covered: Vec<i32> = linear
+            .iter()
+            .flat_map(|interval| *interval.start()..*interval.limit())
+            .collect();
+        for (index, point) in covered.iter().enumerate() {
+            assert_eq!(ivals.nth_covered_point(index as u32), Some(*point));
+        }
+        assert_eq!(ivals.nth_covered_point(covered.len() as u32), None);
+
+        let rebuilt = IndexedIntervals::from(ivals.as_intervals().clone());
+        rebuilt.check_invariants();
+        assert_eq!(rebuilt.total_len(), ivals.total_len());
+    }
+
+    /// A stale cached length is caught even when the counts still agree.
+    #[test]
+    #[should_panic(expected = "cached subtree length")]
+    fn test_check_invariants_catches_stale_len() {
+        let mut ivals = IndexedIntervals::<i64>::new();
+        ivals.add(0, 10);
+        ivals.add(20, 25);
+        ivals.tree.root.as_mut().unwrap().len = 1;
+        ivals.check_invariants();
+    }
+}
@@ -0,0 +1,243 @@
+use std::ops::{Range, RangeInclusive};
+
+use crate::{IntervalError, Step};
+
+/// A half-open interval `[start, limit)`; i.e. the values `v` with `start <= v < limit`.
+///
+/// Intervals always have `start <= limit`; an interval with `start == limit` is empty. This is
+/// the item type returned by the [`CoalescedIntervals`](crate::CoalescedIntervals) queries and
+/// iterators.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interval<T> {
+    start: T,
+    limit: T,
+}
+
+impl<T> Interval<T> {
+    /// Creates the interval `[start, limit)` where the caller already knows `start <= limit`;
+    /// e.g. because it came out of a well-formed interval set.
+    pub(crate) fn new_unchecked(start: T, limit: T) -> Self {
+        Interval { start, limit }
+    }
+
+    /// Returns the (inclusive) start of the interval.
+    pub fn start(&self) -> &T {
+        &self.start
+    }
+
+    /// Returns the (exclusive) limit of the interval.
+    pub fn limit(&self) -> &T {
+        &self.limit
+    }
+}
+
+impl<T: Ord> Interval<T> {
+    /// Creates the interval `[start, limit)` -- panics if `start > limit`.
+    pub fn new(start: T, limit: T) -> Self {
+        assert!(start <= limit);
+        Interval { start, limit }
+    }
+
+    /// Fallible form of [`new`](Self::new): returns [`IntervalError::InvertedBounds`] instead of
+    /// panicking when `start > limit`.
+    pub fn try_new(start: T, limit: T) -> Result<Self, IntervalError<T>> {
+        if start <= limit {
+            Ok(Interval { start, limit })
+        } else {
+            Err(IntervalError::InvertedBounds { start, limit })
+        }
+    }
+
+    /// Returns whether the interval holds no values; i.e. `start == limit`.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.limit
+    }
+
+    /// Returns whether `value` is in the interval.
+    pub fn contains(&self, value: &T) -> bool {
+        self.start <= *value && *value < self.limit
+    }
+
+    /// Returns whether the two intervals have any value in common; empty intervals overlap
+    /// nothing.
+    pub fn overlaps(&self, other: &Self) -> bool {
+        !self.is_empty()
+            && !other.is_empty()
+            && self.start < other.limit
+            && other.start < self.limit
+    }
+
+    /// Returns whether one interval's limit is the other's start, so that together they would
+    /// coalesce into a single interval with no value in common; empty intervals abut nothing.
+    pub fn abuts(&self, other: &Self) -> bool {
+        !self.is_empty()
+            && !other.is_empty()
+            && (self.limit == other.start || other.limit == self.start)
+    }
+}
+
+impl<T: Ord + Clone> Interval<T> {
+    /// Returns the values the two intervals have in common, or `None` if they do not overlap.
+    pub fn intersect(&self, other: &Self) -> Option<Self> {
+        if self.overlaps(other) {
+            Some(Interval {
+                start: std::cmp::max(&self.start, &other.start).clone(),
+                limit: std::cmp::min(&self.limit, &other.limit).clone(),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the smallest interval containing both intervals, including any hole between them.
+    pub fn hull(&self, other: &Self) -> Self {
+        Interval {
+            start: std::cmp::min(&self.start, &other.start).clone(),
+            limit: std::cmp::max(&self.limit, &other.limit).clone(),
+        }
+    }
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for Interval<T> {
+    /// Formats the interval as `[start, limit)`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}, {})", self.start, self.limit)
+    }
+}
+
+/// Allows comparing against `(start, limit)` tuples; e.g. `ivals.to_vec() == [(0, 3)]`.
+impl<T: PartialEq> PartialEq<(T, T)> for Interval<T> {
+    fn eq(&self, other: &(T, T)) -> bool {
+        self.start == other.0 && self.limit == other.1
+    }
+}
+
+/// Panics if `start > limit`, as with [`Interval::new`].
+/// Returns [`IntervalError::InvertedBounds`] if `start > limit`; see [`Interval::try_new`].
+impl<T: Ord> TryFrom<(T, T)> for Interval<T> {
+    type Error = IntervalError<T>;
+
+    fn try_from((start, limit): (T, T)) -> Result<Self, IntervalError<T>> {
+        Interval::try_new(start, limit)
+    }
+}
+
+impl<T> From<Interval<T>> for (T, T) {
+    fn from(interval: Interval<T>) -> Self {
+        (interval.start, interval.limit)
+    }
+}
+
+/// Returns [`IntervalError::InvertedBounds`] if `start > end`; see [`Interval::try_new`].
+impl<T: Ord> TryFrom<Range<T>> for Interval<T> {
+    type Error = IntervalError<T>;
+
+    fn try_from(range: Range<T>) -> Result<Self, IntervalError<T>> {
+        Interval::try_new(range.start, range.end)
+    }
+}
+
+impl<T> From<Interval<T>> for Range<T> {
+    fn from(interval: Interval<T>) -> Self {
+        interval.start..interval.limit
+    }
+}
+
+/// Converts `[first, last]` to `[first, last + 1)`; returns [`IntervalError::Overflow`] if
+/// `last` is the maximum value.
+impl<T: Step> TryFrom<RangeInclusive<T>> for Interval<T> {
+    type Error = IntervalError<T>;
+
+    fn try_from(range: RangeInclusive<T>) -> Result<Self, IntervalError<T>> {
+        let (first, last) = range.into_inner();
+        match last.successor() {
+            Some(limit) => Interval::try_new(first, limit),
+            None => Err(IntervalError::Overflow { value: last }),
+        }
+    }
+}
+
+/// Converts `[start, limit)` to `[start, limit - 1]`; returns [`IntervalError::Overflow`] if
+/// `limit` is the minimum value (which only an empty interval can have).
+impl<T: Step> TryFrom<Interval<T>> for RangeInclusive<T> {
+    type Error = IntervalError<T>;
+
+    fn try_from(interval: Interval<T>) -> Result<Self, IntervalError<T>> {
+        match interval.limit.predecessor() {
+            Some(last) => Ok(interval.start..=last),
+            None => Err(IntervalError::Overflow {
+                value: interval.limit,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_predicates() {
+        let a = Interval::new(0, 5);
+        assert_eq!((*a.start(), *a.limit()), (0, 5));
+        assert_eq!(a.len(), 5u32);
+        assert!(!a.is_empty());
+        assert!(Interval::new(3, 3).is_empty());
+        assert!(a.contains(&0));
+        assert!(!a.contains(&5));
+
+        let b = Interval::new(5, 8);
+        assert!(!a.overlaps(&b));
+        assert!(a.abuts(&b) && b.abuts(&a));
+        assert_eq!(a.intersect(&b), None);
+        assert_eq!(a.hull(&b), Interval::new(0, 8));
+
+        let c = Interval::new(3, 10);
+        assert!(a.overlaps(&c));
+        assert!(!a.abuts(&c));
+        assert_eq!(a.intersect(&c), Some(Interval::new(3, 5)));
+        assert!(!a.overlaps(&Interval::new(2, 2)));
+
+        // Empty intervals abut nothing, even at an endpoint.
+        assert!(!a.abuts(&Interval::new(5, 5)));
+        assert!(!Interval::new(0, 0).abuts(&a));
+        assert!(!Interval::new(5, 5).abuts(&Interval::new(5, 5)));
+
+        assert_eq!(
+            Interval::try_new(2, 1),
+            Err(IntervalError::InvertedBounds { start: 2, limit: 1 })
+        );
+        assert_eq!(a.to_string(), "[0, 5)");
+    }
+
+    #[test]
+    fn test_conversions() {
+        let a = Interval::try_from((0, 5)).unwrap();
+        assert_eq!(a, (0, 5));
+        assert_eq!(Interval::try_from(0..5), Ok(a));
+        assert_eq!(<(i32, i32)>::from(a), (0, 5));
+        assert_eq!(Range::from(a), 0..5);
+        let set: crate::CoalescedIntervals<i32> = [a, Interval::new(5, 6)].into_iter().collect();
+        assert_eq!(set.to_vec(), [Interval::new(0, 6)]);
+
+        assert_eq!(Interval::try_from(0..=4), Ok(a));
+        assert_eq!(RangeInclusive::try_from(a), Ok(0..=4));
+        assert_eq!(
+            Interval::try_from(0..=u8::MAX),
+            Err(IntervalError::Overflow { value: u8::MAX })
+        );
+        assert_eq!(
+            RangeInclusive::try_from(Interval::new(0u8, 0)),
+            Err(IntervalError::Overflow { value: 0 })
+        );
+    }
+
+    #[test]
+    fn test_inverted_conversions() {
+        let inverted = Err(IntervalError::InvertedBounds { start: 3, limit: 1 });
+        assert_eq!(Interval::try_from((3, 1)), inverted);
+        #[allow(clippy::reversed_empty_ranges)]
+        let range = 3..1;
+        assert_eq!(Interval::try_from(range), inverted);
+    }
+}
@@ -0,0 +1,474 @@
+use core::ops::Bound;
+use std::collections::BTreeMap;
+
+use crate::IntervalError;
+
+/// Sibling of [`CoalescedIntervals`](crate::CoalescedIntervals) where every `[start, limit)` run
+/// carries a value.
+///
+/// Inserting over an existing range overwrites the values in that range, and neighboring runs are
+/// coalesced whenever they abut and hold equal values. For example, if I insert `[0, 2) => 'r'`
+/// and then `[2, 4) => 'r'` I should observe a single run `[0, 4) => 'r'`, whereas inserting
+/// `[1, 3) => 'w'` over that leaves three runs.
+///
+/// Implementation note: as with `CoalescedIntervals` we use two btrees, one with the starts as
+/// the keys (also holding the run's value) and one with limits as the keys.
+pub struct CoalescedIntervalMap<K, V> {
+    start_to_run: BTreeMap<K, (K, V)>,
+    limit_to_start: BTreeMap<K, K>,
+}
+
+impl<K: std::cmp::Ord + Clone, V: Clone + PartialEq> Default for CoalescedIntervalMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: std::cmp::Ord + Clone, V: Clone + PartialEq> CoalescedIntervalMap<K, V> {
+    /// Creates a new (empty) interval map.
+    pub fn new() -> Self {
+        CoalescedIntervalMap {
+            start_to_run: BTreeMap::new(),
+            limit_to_start: BTreeMap::new(),
+        }
+    }
+
+    /// Checks invariants for this data structure -- panics via `assert!` if there are internal
+    /// inconsistencies.
+    pub fn check_invariants(&self) {
+        // There should be no empty-sized runs, and data should be reflected symmetrically in both
+        // maps.
+        for (start, (limit, _value)) in self.start_to_run.iter() {
+            assert!(start < limit);
+            assert!(self.limit_to_start[limit] == *start);
+        }
+        for (limit, start) in self.limit_to_start.iter() {
+            assert!(self.start_to_run[start].0 == *limit);
+        }
+        // Runs should be disjoint, and abutting runs should hold different values (or they would
+        // have been coalesced).
+        let mut prev: Option<(&K, &V)> = None;
+        for (start, (limit, value)) in self.start_to_run.iter() {
+            if let Some((prev_limit, prev_value)) = prev {
+                assert!(prev_limit <= start);
+                assert!(prev_limit != start || prev_value != value);
+            }
+            prev = Some((limit, value));
+        }
+    }
+
+    /// Inserts the run into both underlying mappings.
+    fn insert_record(&mut self, start: K, limit: K, value: V) {
+        assert!(start < limit);
+        self.start_to_run
+            .insert(start.clone(), (limit.clone(), value));
+        self.limit_to_start.insert(limit, start);
+    }
+
+    /// Removes the run from both mappings that has a start at `value` -- panics if no such run
+    /// exists.
+    fn remove_with_start_at(&mut self, value: &K) -> (K, K, V) {
+        if let Some((start, (limit, value))) = self.start_to_run.remove_entry(value) {
+            self.limit_to_start.remove(&limit);
+            (start, limit, value)
+        } else {
+            panic!("Attempted to remove start that was not present in map");
+        }
+    }
+
+    /// Rewrites the values over `[start, limit)`.
+    ///
+    /// `update` is called once for each maximal sub-range of the window, in ascending order, with
+    /// the value currently held there (or `None` for holes); it returns the value that sub-range
+    /// should hold afterwards (or `None` to leave a hole). The sub-ranges outside of the window
+    /// keep their values, and the result is re-coalesced with its neighbors.
+    pub(crate) fn update_range(
+        &mut self,
+        start: K,
+        limit: K,
+        mut update: impl FnMut(Option<&V>) -> Option<V>,
+    ) {
+        assert!(start <= limit);
+        if start == limit {
+            return;
+        }
+
+        // Pull out every run that overlaps the window, as well as the runs that abut it on
+        // either side, since those may need to coalesce with the result.
+        let first_start = match self.find_run(&start) {
+            Some((containing_start, _, _)) if *containing_start < start => containing_start.clone(),
+            _ => self
+                .limit_to_start
+                .get(&start)
+                .cloned()
+                .unwrap_or_else(|| start.clone()),
+        };
+        let affected_starts: Vec<K> = self
+            .start_to_run
+            .range((Bound::Included(&first_start), Bound::Included(&limit)))
+            .map(|(run_start, _)| run_start.clone())
+            .collect();
+        let affected: Vec<(K, K, V)> = affected_starts
+            .iter()
+            .map(|run_start| self.remove_with_start_at(run_start))
+            .collect();
+
+        // Lay the replacement runs out in order, calling `update` on the portions inside the
+        // window (including the holes between existing runs).
+        let mut pieces: Vec<(K, K, V)> = vec![];
+        let mut cursor = start.clone();
+        let mut push_updated =
+            |pieces: &mut Vec<(K, K, V)>, piece_start: K, piece_limit: K, current: Option<&V>| {
+                if piece_start < piece_limit {
+                    if let Some(value) = update(current) {
+                        pieces.push((piece_start, piece_limit, value));
+                    }
+                }
+            };
+        for (run_start, run_limit, value) in affected {
+            if run_start < start {
+                pieces.push((
+                    run_start.clone(),
+                    std::cmp::min(&run_limit, &start).clone(),
+                    value.clone(),
+                ));
+            }
+            let overlap_start = std::cmp::max(&run_start, &start).clone();
+            let overlap_limit = std::cmp::min(&run_limit, &limit).clone();
+            if overlap_start < overlap_limit {
+                push_updated(&mut pieces, cursor, overlap_start.clone(), None);
+                push_updated(
+                    &mut pieces,
+                    overlap_start,
+                    overlap_limit.clone(),
+                    Some(&value),
+                );
+                cursor = overlap_limit;
+            }
+            if run_limit > limit {
+                push_updated(&mut pieces, cursor, limit.clone(), None);
+                cursor = limit.clone();
+                pieces.push((std::cmp::max(run_start, limit.clone()), run_limit, value));
+            }
+        }
+        push_updated(&mut pieces, cursor, limit, None);
+
+        // Coalesce abutting pieces that ended up with equal values.
+        let mut coalesced: Vec<(K, K, V)> = Vec::with_capacity(pieces.len());
+        for (piece_start, piece_limit, value) in pieces {
+            match coalesced.last_mut() {
+                Some((_, last_limit, last_value))
+                    if *last_limit == piece_start && *last_value == value =>
+                {
+                    *last_limit = piece_limit;
+                }
+                _ => coalesced.push((piece_start, piece_limit, value)),
+            }
+        }
+        for (piece_start, piece_limit, value) in coalesced {
+            self.insert_record(piece_start, piece_limit, value);
+        }
+    }
+
+    /// Sets the value of `[start, limit)` to `value`, overwriting whatever was there before.
+    pub fn insert(&mut self, start: K, limit: K, value: V) {
+        assert!(start <= limit);
+        self.update_range(start, limit, |_| Some(value.clone()));
+    }
+
+    /// Fallible form of [`insert`](Self::insert): returns [`IntervalError::InvertedBounds`]
+    /// instead of panicking when `start > limit`.
+    pub fn try_insert(&mut self, start: K, limit: K, value: V) -> Result<(), IntervalError<K>> {
+        IntervalError::check_bounds(&start, &limit)?;
+        self.insert(start, limit, value);
+        Ok(())
+    }
+
+    /// Merges `value` into `[start, limit)`, combining it with the values already there.
+    ///
+    /// Holes within the window take `value` as-is; existing runs are split at the window edges
+    /// and the overlapped portions take `combine(existing, &value)`. Abutting sub-runs that end up
+    /// with equal values are coalesced as usual. For example, `|a, b| *a.max(b)` computes the
+    /// maximum covering each key, and `|a, b| a + b` sums them.
+    pub fn insert_with(
+        &mut self,
+        start: K,
+        limit: K,
+        value: V,
+        mut combine: impl FnMut(&V, &V) -> V,
+    ) {
+        assert!(start <= limit);
+        self.update_range(start, limit, |existing| match existing {
+            Some(existing) => Some(combine(existing, &value)),
+            None => Some(value.clone()),
+        });
+    }
+
+    /// Fallible form of [`insert_with`](Self::insert_with): returns
+    /// [`IntervalError::InvertedBounds`] instead of panicking when `start > limit`.
+    pub fn try_insert_with(
+        &mut self,
+        start: K,
+        limit: K,
+        value: V,
+        combine: impl FnMut(&V, &V) -> V,
+    ) -> Result<(), IntervalError<K>> {
+        IntervalError::check_bounds(&start, &limit)?;
+        self.insert_with(start, limit, value, combine);
+        Ok(())
+    }
+
+    /// Removes `[start, limit)` from the map, trimming or splitting the runs it overlaps.
+    pub fn remove(&mut self, start: K, limit: K) {
+        assert!(start <= limit);
+        self.update_range(start, limit, |_| None);
+    }
+
+    /// Fallible form of [`remove`](Self::remove): returns [`IntervalError::InvertedBounds`]
+    /// instead of panicking when `start > limit`.
+    pub fn try_remove(&mut self, start: K, limit: K) -> Result<(), IntervalError<K>> {
+        IntervalError::check_bounds(&start, &limit)?;
+        self.remove(start, limit);
+        Ok(())
+    }
+
+    /// Returns the run that contains `key` by reference, or `None` if there is none.
+    fn find_run(&self, key: &K) -> Option<(&K, &K, &V)> {
+        // The only candidate is the first run whose limit is after `key`.
+        let (limit, start) = self
+            .limit_to_start
+            .range((Bound::Excluded(key), Bound::Unbounded))
+            .next()?;
+        if start <= key {
+            Some((start, limit, &self.start_to_run[start].1))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the run `(start, limit, value)` that contains `key`, or `None` if there is none.
+    pub fn get_run_containing(&self, key: K) -> Option<(K, K, &V)> {
+        self.find_run(&key)
+            .map(|(start, limit, value)| (start.clone(), limit.clone(), value))
+    }
+
+    /// Returns the value at `key`, or `None` if `key` is not covered by any run.
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.find_run(&key).map(|(_, _, value)| value)
+    }
+
+    /// Returns an iterator over the `(start, limit, value)` runs in sorted (ascending) order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (K, K, &V)> + '_ {
+        self.start_to_run
+            .iter()
+            .map(|(start, (limit, value))| (start.clone(), limit.clone(), value))
+    }
+
+    /// Returns an iterator over the `(start, limit, value)` runs that overlap `[start, limit)`, in
+    /// sorted (ascending) order.
+    ///
+    /// Runs that merely abut the query window are not included. As with
+    /// [`CoalescedIntervals::iter_overlapping`](crate::CoalescedIntervals::iter_overlapping), when
+    /// the query window is empty this yields the run containing the point `start`, if any.
+    pub fn iter_overlapping(
+        &self,
+        start: K,
+        limit: K,
+    ) -> impl DoubleEndedIterator<Item = (K, K, &V)> + '_ {
+        assert!(start <= limit);
+        let range = match self.find_run(&start) {
+            Some((containing_start, _, _)) if start == limit => (
+                Bound::Included(containing_start.clone()),
+                Bound::Included(containing_start.clone()),
+            ),
+            Some((containing_start, _, _)) => (
+                Bound::Included(containing_start.clone()),
+                Bound::Excluded(limit),
+            ),
+            None => (Bound::Included(start), Bound::Excluded(limit)),
+        };
+        self.start_to_run
+            .range(range)
+            .map(|(start, (limit, value))| (start.clone(), limit.clone(), value))
+    }
+
+    /// Fallible form of [`iter_overlapping`](Self::iter_overlapping): returns
+    /// [`IntervalError::InvertedBounds`] instead of panicking when `start > limit`.
+    pub fn try_iter_overlapping(
+        &self,
+        start: K,
+        limit: K,
+    ) -> Result<impl DoubleEndedIterator<Item = (K, K, &V)> + '_, IntervalError<K>> {
+        IntervalError::check_bounds(&start, &limit)?;
+        Ok(self.iter_overlapping(start, limit))
+    }
+
+    /// Converts the current map to a vector of `(start, limit, value)` runs in sorted
+    /// (ascending) order.
+    pub fn to_vec(&self) -> Vec<(K, K, V)> {
+        self.iter()
+            .map(|(start, limit, value)| (start, limit, value.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Abutting runs with equal values coalesce; different values stay separate.
+    #[test]
+    fn test_insert_coalesces_equal_values() {
+        let _ = env_logger::try_init();
+        let mut map = CoalescedIntervalMap::<i64, char>::new();
+        map.insert(0, 2, 'r');
+        map.insert(4, 6, 'r');
+        map.insert(2, 4, 'r');
+        assert_eq!(map.to_vec(), [(0, 6, 'r')]);
+        map.insert(6, 8, 'w');
+        assert_eq!(map.to_vec(), [(0, 6, 'r'), (6, 8, 'w')]);
+        map.check_invariants();
+    }
+
+    /// Inserting over existing runs overwrites the overlapped portion only.
+    #[test]
+    fn test_insert_overwrites() {
+        let mut map = CoalescedIntervalMap::<i64, char>::new();
+        map.insert(0, 10, 'r');
+        map.insert(3, 5, 'w');
+        assert_eq!(map.to_vec(), [(0, 3, 'r'), (3, 5, 'w'), (5, 10, 'r')]);
+        assert_eq!(map.get(4), Some(&'w'));
+        assert_eq!(map.get_run_containing(5), Some((5, 10, &'r')));
+        assert_eq!(map.get(10), None);
+        map.check_invariants();
+
+        // Overwriting the middle back to the surrounding value re-coalesces.
+        map.insert(2, 6, 'r');
+        assert_eq!(map.to_vec(), [(0, 10, 'r')]);
+
+        // Overwriting across a hole fills it.
+        map.insert(12, 14, 'x');
+        map.insert(8, 13, 'w');
+        assert_eq!(map.to_vec(), [(0, 8, 'r'), (8, 13, 'w'), (13, 14, 'x')]);
+        map.check_invariants();
+
+        // Overwriting the start of a run coalesces with the run abutting on the left.
+        map.insert(8, 10, 'r');
+        assert_eq!(map.to_vec(), [(0, 10, 'r'), (10, 13, 'w'), (13, 14, 'x')]);
+        map.check_invariants();
+    }
+
+    /// Combining with `max` splits the overlapped runs and re-coalesces equal results.
+    #[test]
+    fn test_insert_with_max() {
+        let mut map = CoalescedIntervalMap::<i64, u32>::new();
+        map.insert(0, 4, 1);
+        map.insert(4, 8, 5);
+        map.insert_with(2, 10, 3, |a, b| *a.max(b));
+        assert_eq!(map.to_vec(), [(0, 2, 1), (2, 4, 3), (4, 8, 5), (8, 10, 3)]);
+        map.insert_with(0, 2, 3, |a, b| *a.max(b));
+        assert_eq!(map.to_vec(), [(0, 4, 3), (4, 8, 5), (8, 10, 3)]);
+        map.check_invariants();
+    }
+
+    /// Combining with addition accumulates over overlapping inserts.
+    #[test]
+    fn test_insert_with_sum() {
+        let mut map = CoalescedIntervalMap::<i64, u32>::new();
+        map.insert_with(0, 4, 1, |a, b| a + b);
+        map.insert_with(2, 6, 1, |a, b| a + b);
+        map.insert_with(4, 6, 1, |a, b| a + b);
+        assert_eq!(map.to_vec(), [(0, 2, 1), (2, 6, 2)]);
+        assert_eq!(map.get(5), Some(&2));
+        map.check_invariants();
+    }
+
+    #[test]
+    fn test_try_inverted_bounds() {
+        let mut map = CoalescedIntervalMap::<i64, char>::new();
+        let inverted = Err(IntervalError::InvertedBounds { start: 2, limit: 1 });
+        assert_eq!(map.try_insert(2, 1, 'r'), inverted);
+        assert_eq!(map.try_insert_with(2, 1, 'r', |a, _| *a), inverted);
+        assert_eq!(map.try_remove(2, 1), inverted);
+        assert_eq!(map.try_iter_overlapping(2, 1).err(), inverted.err());
+        assert_eq!(map.try_insert(0, 2, 'r'), Ok(()));
+        assert_eq!(map.try_remove(1, 2), Ok(()));
+        assert_eq!(map.to_vec(), [(0, 1, 'r')]);
+        assert_eq!(
+            map.try_iter_overlapping(0, 2).unwrap().collect::<Vec<_>>(),
+            [(0, 1, &'r')]
+        );
+    }
+
+    #[test]
+    fn test_iter_overlapping() {
+        let mut map = CoalescedIntervalMap::<i64, char>::new();
+        map.insert(0, 2, 'r');
+        map.insert(2, 4, 'w');
+        map.insert(6, 8, 'x');
+        let overlapping = |start, limit| {
+            map.iter_overlapping(start, limit)
+                .map(|(s, l, v)| (s, l, *v))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(overlapping(1, 7), [(0, 2, 'r'), (2, 4, 'w'), (6, 8, 'x')]);
+        assert_eq!(overlapping(2, 6), [(2, 4, 'w')]);
+        assert_eq!(overlapping(4, 6), []);
+        assert_eq!(overlapping(7, 7), [(6, 8, 'x')]);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut map = CoalescedIntervalMap::<i64, char>::new();
+        map.insert(0, 4, 'r');
+        map.insert(4, 8, 'w');
+        map.remove(2, 6);
+        assert_eq!(map.to_vec(), [(0, 2, 'r'), (6, 8, 'w')]);
+        assert_eq!(map.get(3), None);
+        map.remove(-1, 0);
+        map.remove(1, 1);
+        assert_eq!(map.to_vec(), [(0, 2, 'r'), (6, 8, 'w')]);
+        map.check_invariants();
+    }
+
+    /// Keys only need to be `Ord + Clone`; e.g. byte-string key spans in a sorted table.
+    #[test]
+    fn test_byte_string_keys() {
+        let key = |k: &[u8]| k.to_vec();
+        let mut map = CoalescedIntervalMap::<Vec<u8>, u32>::new();
+        map.insert(key(b"a"), key(b"m"), 1);
+        map.insert(key(b"m"), key(b"z"), 1);
+        map.insert_with(key(b"c"), key(b"d"), 1, |a, b| a + b);
+        assert_eq!(
+            map.to_vec(),
+            [
+                (key(b"a"), key(b"c"), 1),
+                (key(b"c"), key(b"d"), 2),
+                (key(b"d"), key(b"z"), 1)
+            ]
+        );
+        assert_eq!(map.get(key(b"cat")), Some(&2));
+        assert_eq!(map.get(key(b"zoo")), None);
+        map.remove(key(b"b"), key(b"y"));
+        assert_eq!(
+            map.to_vec(),
+            [(key(b"a"), key(b"b"), 1), (key(b"y"), key(b"z"), 1)]
+        );
+        map.check_invariants();
+    }
+
+    /// Nothing requires `K: Debug`.
+    #[test]
+    fn test_non_debug_keys() {
+        #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+        struct Key(u32);
+
+        let mut map = CoalescedIntervalMap::<Key, char>::new();
+        map.insert(Key(0), Key(4), 'r');
+        map.insert(Key(2), Key(6), 'w');
+        map.remove(Key(5), Key(6));
+        assert_eq!(map.iter().count(), 2);
+        assert_eq!(map.get(Key(3)), Some(&'w'));
+        map.check_invariants();
+    }
+}
@@ -1,6 +1,27 @@
 use core::ops::Bound;
 use std::collections::BTreeMap;
 
+mod coverage;
+mod cut;
+mod discrete;
+mod error;
+mod float;
+mod indexed;
+mod interval;
+mod interval_map;
+mod measure;
+mod text;
+
+pub use coverage::CoalescedCoverage;
+pub use cut::{CoalescedRanges, Cut, Dense, Domain};
+pub use discrete::{DiscreteIntervals, Step};
+pub use error::{IntervalError, InvariantViolation, ParseError, ParseErrorReason};
+pub use float::{NanError, NotNan};
+pub use indexed::IndexedIntervals;
+pub use interval::Interval;
+pub use interval_map::CoalescedIntervalMap;
+pub use measure::Measure;
+
 /// This is a conceptually simple data structure designed for the case where you have intervals
 /// that you'd like to coalesce into maximal contiguous runs.
 ///
@@ -9,12 +30,29 @@ use std::collections::BTreeMap;
 ///
 /// Implementation note: we use two btrees, one with the starts as the keys and one with limits as
 /// the keys.
+#[derive(Clone)]
 pub struct CoalescedIntervals<T> {
     start_to_limit: BTreeMap<T, T>,
     limit_to_start: BTreeMap<T, T>,
 }
 
-impl<T: Copy + std::cmp::Ord + std::fmt::Debug> CoalescedIntervals<T> {
+impl<T: std::cmp::Ord + Clone> Default for CoalescedIntervals<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: std::cmp::Ord + Clone + std::fmt::Debug> CoalescedIntervals<T> {
+    /// Checks interval invariants for this data structure -- panics if there are internal
+    /// inconsistencies; see [`validate`](Self::validate) for a non-panicking form.
+    pub fn check_invariants(&self) {
+        if let Err(violation) = self.validate() {
+            panic!("Interval invariant violated: {}", violation);
+        }
+    }
+}
+
+impl<T: std::cmp::Ord + Clone> CoalescedIntervals<T> {
     /// Creates a new (empty) set of maximally coalesced intervals.
     pub fn new() -> Self {
         CoalescedIntervals {
@@ -23,248 +61,407 @@ impl<T: Copy + std::cmp::Ord + std::fmt::Debug> CoalescedIntervals<T> {
         }
     }
 
-    /// Checks interval invariants for this data structure -- panics via `assert!` if there are
-    /// internal inconsistencies.
-    pub fn check_invariants(&self) {
-        // There should be no empty-sized intervals, and data should be reflected symmetrically in
-        // both maps.
+    /// Checks interval invariants for this data structure, returning the first inconsistency
+    /// found rather than panicking.
+    ///
+    /// Intervals must be non-empty, reflected symmetrically in both underlying mappings, and
+    /// maximally coalesced; i.e. each interval's limit is strictly less than the next interval's
+    /// start.
+    pub fn validate(&self) -> Result<(), InvariantViolation<T>> {
         for (start, limit) in self.start_to_limit.iter() {
-            assert!(start != limit);
-            assert!(self.limit_to_start[&limit] == *start);
+            if start == limit {
+                return Err(InvariantViolation::Empty {
+                    start: start.clone(),
+                    limit: limit.clone(),
+                });
+            }
+            if start > limit {
+                return Err(InvariantViolation::Inverted {
+                    start: start.clone(),
+                    limit: limit.clone(),
+                });
+            }
+            if self.limit_to_start.get(limit) != Some(start) {
+                return Err(InvariantViolation::Asymmetric {
+                    start: start.clone(),
+                    limit: limit.clone(),
+                });
+            }
         }
         for (limit, start) in self.limit_to_start.iter() {
-            assert!(start != limit);
-            assert!(self.start_to_limit[&start] == *limit);
+            if self.start_to_limit.get(start) != Some(limit) {
+                return Err(InvariantViolation::Asymmetric {
+                    start: start.clone(),
+                    limit: limit.clone(),
+                });
+            }
+        }
+        // The starts are sorted by construction, so it's sufficient to compare neighbors.
+        let mut prev: Option<(&T, &T)> = None;
+        for (start, limit) in self.start_to_limit.iter() {
+            if let Some((prev_start, prev_limit)) = prev {
+                let pair = || {
+                    (
+                        Interval::new_unchecked(prev_start.clone(), prev_limit.clone()),
+                        Interval::new_unchecked(start.clone(), limit.clone()),
+                    )
+                };
+                if prev_limit > start {
+                    let (first, second) = pair();
+                    return Err(InvariantViolation::Overlapping { first, second });
+                }
+                if prev_limit == start {
+                    let (first, second) = pair();
+                    return Err(InvariantViolation::Abutting { first, second });
+                }
+            }
+            prev = Some((start, limit));
         }
+        Ok(())
+    }
+
+    /// With the `paranoid` feature enabled, validates the invariants after a mutation and panics
+    /// naming the `operation` that broke them; otherwise this is a no-op.
+    ///
+    /// Note `T` is not required to be `Debug`, so only the kind of violation is reported.
+    #[inline]
+    fn paranoid_check(&self, operation: &str) {
+        #[cfg(feature = "paranoid")]
+        if let Err(violation) = self.validate() {
+            panic!(
+                "Interval invariant violated after {}: {}",
+                operation,
+                violation.reason()
+            );
+        }
+        #[cfg(not(feature = "paranoid"))]
+        let _ = operation;
     }
 
     /// To be dominated by this interval the candidate_start must be >= start and candidate_limit
     /// must be <= limit.
-    fn remove_intervals_dominated_by(&mut self, start: T, limit: T) {
+    fn remove_intervals_dominated_by(&mut self, start: &T, limit: &T) {
         assert!(start <= limit);
         let mut dominated = vec![];
         for (candidate_start, candidate_limit) in self
             .start_to_limit
             .range((Bound::Included(start), Bound::Excluded(limit)))
         {
-            if *candidate_limit <= limit {
-                dominated.push((*candidate_start, *candidate_limit));
+            if candidate_limit <= limit {
+                dominated.push(candidate_start.clone());
             } else {
                 // candidate_limit > limit, so we can stop looking
                 break;
             }
         }
-        for (s, l) in dominated {
-            self.start_to_limit.remove(&s);
-            self.limit_to_start.remove(&l);
+        for s in dominated {
+            self.remove_with_start_at(&s);
         }
     }
 
-    fn is_dominated_by_existing(&self, start: T, limit: T) -> bool {
+    fn is_dominated_by_existing(&self, start: &T, limit: &T) -> bool {
         assert!(start <= limit);
         // Look at the first interval that ends at-or-after limit to see if it dominates.
-        for (_existing_limit, existing_start) in self
+        if let Some((_existing_limit, existing_start)) = self
             .limit_to_start
             .range((Bound::Included(limit), Bound::Unbounded))
+            .next()
         {
-            if *existing_start <= start {
+            if existing_start <= start {
                 return true;
-            } else {
-                break;
             }
         }
-        // Look at the first interval that start at-or-before start to see if it dominates.
-        for (_existing_start, existing_limit) in self
+        // Look at the last interval that starts at-or-before start to see if it dominates.
+        if let Some((_existing_start, existing_limit)) = self
             .start_to_limit
             .range((Bound::Unbounded, Bound::Included(start)))
+            .next_back()
         {
-            if *existing_limit >= limit {
+            if existing_limit >= limit {
                 return true;
-            } else {
-                break;
             }
         }
-        return false;
+        false
     }
 
     /// Inserts the `[start, limit)` interval into both underlying mappings.
     fn insert_record(&mut self, start: T, limit: T) {
         assert!(start <= limit);
-        log::debug!("inserting record: {:?}, {:?}", start, limit);
-        self.start_to_limit.insert(start, limit);
+        self.start_to_limit.insert(start.clone(), limit.clone());
         self.limit_to_start.insert(limit, start);
     }
 
-    /// Removes the interval from both mappings that has a start at `value` -- panics if no such
-    /// interval exists.
-    fn remove_with_start_at(&mut self, value: T) -> T {
-        if let Some((start, limit)) = self.start_to_limit.remove_entry(&value) {
+    /// Removes the interval from both mappings that has a start at `value`, returning its limit
+    /// -- panics if no such interval exists.
+    fn remove_with_start_at(&mut self, value: &T) -> T {
+        if let Some(limit) = self.start_to_limit.remove(value) {
             self.limit_to_start.remove(&limit);
-            log::debug!("removed: {:?}, {:?}", start, limit);
             limit
         } else {
             panic!("Attempted to remove start that was not present in map");
         }
     }
 
-    fn remove_with_limit_at(&mut self, value: T) -> T {
-        if let Some((limit, start)) = self.limit_to_start.remove_entry(&value) {
-            self.start_to_limit.remove(&start);
-            log::debug!("removed: {:?}, {:?}", start, limit);
-            start
-        } else {
-            panic!("Attempted to remove limit that was not present in map");
-        }
-    }
-
     /// Finds any collision with the left edge of the interval; e.g. where the limit of another
     /// interval is contained within this interval; i.e.
     ///
     /// `start <= other.limit <= limit`
-    fn find_collision_left(&self, start: T, limit: T) -> Option<(T, T)> {
+    fn find_collision_left(&self, start: &T, limit: &T) -> Option<(&T, &T)> {
         assert!(start <= limit);
-        for (other_limit, other_start) in self
-            .limit_to_start
+        self.limit_to_start
             .range((Bound::Included(start), Bound::Included(limit)))
-        {
-            if start <= *other_limit && *other_limit <= limit {
-                return Some((*other_start, *other_limit));
-            }
-        }
-        return None;
+            .next()
+            .map(|(other_limit, other_start)| (other_start, other_limit))
     }
 
     /// Finds any collision with the right edge of the interval; e.g. where the start of another
     /// interval is contained within this interval; i.e.
     ///
     /// `start <= other.start <= limit`
-    fn find_collision_right(&self, start: T, limit: T) -> Option<(T, T)> {
+    fn find_collision_right(&self, start: &T, limit: &T) -> Option<(&T, &T)> {
         assert!(start <= limit);
-        for (other_start, other_limit) in self
-            .start_to_limit
+        self.start_to_limit
             .range((Bound::Included(start), Bound::Included(limit)))
+            .next()
+    }
+
+    /// Returns the interval that contains `value` by reference; see
+    /// [`get_interval_containing`](Self::get_interval_containing).
+    fn find_containing(&self, value: &T) -> Option<(&T, &T)> {
+        // We look at the first interval whose limit is after `value` to see if it overlaps.
+        if let Some((limit, start)) = self
+            .limit_to_start
+            .range((Bound::Excluded(value), Bound::Unbounded))
+            .next()
         {
-            if start <= *other_start && *other_start <= limit {
-                return Some((*other_start, *other_limit));
+            if start <= value {
+                assert!(limit > value);
+                return Some((start, limit));
             }
         }
-        return None;
+
+        // We look at the last interval whose start is at-or-before `value` to see if it overlaps.
+        if let Some((start, limit)) = self
+            .start_to_limit
+            .range((Bound::Unbounded, Bound::Included(value)))
+            .next_back()
+        {
+            if limit > value {
+                assert!(start <= value);
+                return Some((start, limit));
+            }
+        }
+
+        None
+    }
+
+    /// Returns the portions of `[start, limit)` that are covered by the current interval set, in
+    /// ascending order.
+    fn covered_within(&self, start: &T, limit: &T) -> Vec<Interval<T>> {
+        assert!(start <= limit);
+        if start == limit {
+            return vec![];
+        }
+        self.start_to_limit
+            .range(self.overlapping_range(start, limit))
+            .map(|(existing_start, existing_limit)| {
+                Interval::new_unchecked(
+                    std::cmp::max(existing_start, start).clone(),
+                    std::cmp::min(existing_limit, limit).clone(),
+                )
+            })
+            .collect()
     }
 
     /// Adds the interval `[start, limit)` to the current interval set.
-    pub fn add(&mut self, start: T, limit: T) {
+    ///
+    /// Returns the sub-ranges of `[start, limit)` that were not covered before this call, in
+    /// ascending order; i.e. if the interval was already fully covered, the result is empty.
+    pub fn add(&mut self, start: T, limit: T) -> Vec<Interval<T>> {
         assert!(start <= limit);
         // Ignore empty intervals.
         if start == limit {
-            return;
+            return vec![];
         }
 
         // No change necessary if there's already an interval in there that dominates this one.
-        if self.is_dominated_by_existing(start, limit) {
-            return;
+        if self.is_dominated_by_existing(&start, &limit) {
+            return vec![];
         }
 
-        self.remove_intervals_dominated_by(start, limit);
+        let added: Vec<Interval<T>> = self.gaps(start.clone(), limit.clone()).collect();
+
+        self.remove_intervals_dominated_by(&start, &limit);
 
         // If our start is another interval's limit, or our limit is another interval's start, we
         // coalesce them. Note that both may be true simultaneously. We're maximally coalesced as
         // an invariant, so we don' thave to look for additional things that coalesce or are
         // dominated by this new larger block, they would have been colliding which would break the
         // invariant.
+        //
+        // Only the starts of the collided intervals are needed; their limits come back from
+        // removing them.
 
-        let collision_left: Option<(T, T)> = self.find_collision_left(start, limit);
-        let collision_right: Option<(T, T)> = self.find_collision_right(start, limit);
+        let collision_left: Option<T> = self
+            .find_collision_left(&start, &limit)
+            .map(|(other_start, _other_limit)| other_start.clone());
+        let collision_right: Option<T> = self
+            .find_collision_right(&start, &limit)
+            .map(|(other_start, _other_limit)| other_start.clone());
 
-        log::debug!("considering: {:?}, {:?}", start, limit);
-        log::debug!("  collision left:  {:?}", collision_left);
-        log::debug!("  collision right: {:?}", collision_right);
+        log::debug!(
+            "add: collision left: {}, collision right: {}",
+            collision_left.is_some(),
+            collision_right.is_some()
+        );
 
         match (collision_left, collision_right) {
             (None, None) => {
                 self.insert_record(start, limit);
             }
             // Collision on the right edge.
-            (None, Some((collided_start, collided_limit))) => {
-                self.remove_with_start_at(collided_start);
+            (None, Some(collided_start)) => {
+                let collided_limit = self.remove_with_start_at(&collided_start);
                 assert!(collided_limit > limit);
                 self.insert_record(start, collided_limit);
             }
             // Collision on the left edge.
-            (Some((collided_start, _collided_limit)), None) => {
-                self.remove_with_start_at(collided_start);
+            (Some(collided_start), None) => {
+                self.remove_with_start_at(&collided_start);
                 assert!(collided_start < start);
                 self.insert_record(collided_start, limit);
             }
             // Collision on both edges.
-            (Some((left_start, _)), Some((_, right_limit))) => {
-                self.remove_with_start_at(left_start);
-                self.remove_with_limit_at(right_limit);
+            (Some(left_start), Some(right_start)) => {
+                self.remove_with_start_at(&left_start);
+                let right_limit = self.remove_with_start_at(&right_start);
                 assert!(left_start < start);
                 assert!(limit < right_limit);
                 self.insert_record(left_start, right_limit);
             }
         }
+
+        self.paranoid_check("add");
+        added
     }
 
-    /// Returns the interval that contains `value`, or `None` if there is none in the current
-    /// interval set.
+    /// Fallible form of [`add`](Self::add): returns [`IntervalError::InvertedBounds`] instead of
+    /// panicking when `start > limit`.
+    pub fn try_add(&mut self, start: T, limit: T) -> Result<Vec<Interval<T>>, IntervalError<T>> {
+        IntervalError::check_bounds(&start, &limit)?;
+        Ok(self.add(start, limit))
+    }
+
+    /// Removes the interval `[start, limit)` from the current interval set.
     ///
-    /// Note that limits are exclusive, so with the interval set with a single interval `[0, 1)`
-    /// the value `1` is not contained.
-    pub fn get_interval_containing(&self, value: T) -> Option<(T, T)> {
-        // We look at the first interval whose limit is after `value` to see if it overlaps.
-        for (limit, start) in self
-            .limit_to_start
-            .range((Bound::Excluded(value), Bound::Unbounded))
-        {
-            if *start <= value {
-                assert!(*limit > value);
-                return Some((*start, *limit));
-            } else {
-                break;
+    /// Coalesced intervals that straddle the removed region are split in two, and intervals that
+    /// overlap either edge of it are trimmed.
+    ///
+    /// Returns the sub-ranges of `[start, limit)` that were covered before this call (and so were
+    /// actually removed), in ascending order.
+    pub fn remove(&mut self, start: T, limit: T) -> Vec<Interval<T>> {
+        assert!(start <= limit);
+        // Removing an empty interval (or one that falls entirely in a hole) is a no-op.
+        let removed = self.covered_within(&start, &limit);
+        if removed.is_empty() {
+            return removed;
+        }
+
+        log::debug!("remove: {} covered piece(s)", removed.len());
+
+        // An interval that starts before `start` but reaches into the removed region keeps its
+        // prefix; if it also reaches past `limit` it is split and we're done.
+        let straddling_start = match self.find_containing(&start) {
+            Some((existing_start, _existing_limit)) if *existing_start < start => {
+                Some(existing_start.clone())
+            }
+            _ => None,
+        };
+        if let Some(existing_start) = straddling_start {
+            let existing_limit = self.remove_with_start_at(&existing_start);
+            self.insert_record(existing_start, start.clone());
+            if existing_limit > limit {
+                self.insert_record(limit, existing_limit);
+                self.paranoid_check("remove");
+                return removed;
             }
         }
 
-        // We look at the first interval whose start is before `value` to see if it overlaps.
-        for (start, limit) in self
+        // Everything that starts within the removed region goes away, other than a suffix that
+        // extends past `limit`.
+        let overlapping: Vec<T> = self
             .start_to_limit
-            .range((Bound::Unbounded, Bound::Included(value)))
-        {
-            if *limit > value {
-                assert!(*start <= value);
-                return Some((*start, *limit));
-            } else {
-                break;
+            .range((Bound::Included(&start), Bound::Excluded(&limit)))
+            .map(|(existing_start, _existing_limit)| existing_start.clone())
+            .collect();
+        for existing_start in overlapping {
+            let existing_limit = self.remove_with_start_at(&existing_start);
+            if existing_limit > limit {
+                self.insert_record(limit.clone(), existing_limit);
             }
         }
 
-        None
+        self.paranoid_check("remove");
+        removed
+    }
+
+    /// Fallible form of [`remove`](Self::remove): returns [`IntervalError::InvertedBounds`]
+    /// instead of panicking when `start > limit`.
+    pub fn try_remove(&mut self, start: T, limit: T) -> Result<Vec<Interval<T>>, IntervalError<T>> {
+        IntervalError::check_bounds(&start, &limit)?;
+        Ok(self.remove(start, limit))
+    }
+
+    /// Returns the interval that contains `value`, or `None` if there is none in the current
+    /// interval set.
+    ///
+    /// Note that limits are exclusive, so with the interval set with a single interval `[0, 1)`
+    /// the value `1` is not contained.
+    pub fn get_interval_containing(&self, value: T) -> Option<Interval<T>> {
+        self.find_containing(&value)
+            .map(|(start, limit)| Interval::new_unchecked(start.clone(), limit.clone()))
     }
 
     /// Returns the first interval whose start is >= `value`.
     ///
     /// If there is no such interval, `None` is returned.
-    pub fn get_first_start_from(&self, value: T) -> Option<(T, T)> {
-        for (start, limit) in self
-            .start_to_limit
+    pub fn get_first_start_from(&self, value: T) -> Option<Interval<T>> {
+        self.start_to_limit
             .range((Bound::Included(value), Bound::Unbounded))
-        {
-            return Some((*start, *limit));
-        }
-        None
+            .next()
+            .map(|(start, limit)| Interval::new_unchecked(start.clone(), limit.clone()))
     }
 
-    /// Returns the first interval whose limit is < `value`.
+    /// Returns the first interval whose limit is < `value`, walking backwards from `value`; i.e.
+    /// the interval with the greatest limit that is < `value`.
     ///
     /// If there is no such interval, `None` is returned.
-    pub fn get_first_limit_before(&self, value: T) -> Option<(T, T)> {
-        for (limit, start) in self
-            .limit_to_start
+    pub fn get_first_limit_before(&self, value: T) -> Option<Interval<T>> {
+        self.limit_to_start
             .range((Bound::Unbounded, Bound::Excluded(value)))
-        {
-            return Some((*start, *limit));
-        }
-        None
+            .next_back()
+            .map(|(limit, start)| Interval::new_unchecked(start.clone(), limit.clone()))
+    }
+
+    /// Returns the last interval whose start is <= `value`; i.e. the interval with the greatest
+    /// start that is <= `value`.
+    ///
+    /// If there is no such interval, `None` is returned.
+    pub fn get_last_start_before(&self, value: T) -> Option<Interval<T>> {
+        self.start_to_limit
+            .range((Bound::Unbounded, Bound::Included(value)))
+            .next_back()
+            .map(|(start, limit)| Interval::new_unchecked(start.clone(), limit.clone()))
+    }
+
+    /// Returns the first interval whose limit is >= `value`.
+    ///
+    /// If there is no such interval, `None` is returned.
+    pub fn get_first_limit_from(&self, value: T) -> Option<Interval<T>> {
+        self.limit_to_start
+            .range((Bound::Included(value), Bound::Unbounded))
+            .next()
+            .map(|(limit, start)| Interval::new_unchecked(start.clone(), limit.clone()))
     }
 
     /// Returns whether there is a partial overlap in the interval `[start, limit)`.
@@ -275,47 +472,607 @@ impl<T: Copy + std::cmp::Ord + std::fmt::Debug> CoalescedIntervals<T> {
     /// When the interval given is empty, this function returns whether the point is
     /// contained within any interval in this data structure.
     pub fn contains_partial(&self, start: T, limit: T) -> bool {
+        self.overlaps(&start, &limit)
+    }
+
+    /// Implements [`contains_partial`](Self::contains_partial) by reference.
+    fn overlaps(&self, start: &T, limit: &T) -> bool {
         assert!(start <= limit);
         if start == limit {
-            return self.get_interval_containing(start).is_some();
+            return self.find_containing(start).is_some();
         }
 
         if self.is_dominated_by_existing(start, limit) {
             return true;
         }
 
-        match self.get_first_start_from(start) {
-            Some((next_start, _next_limit)) => {
-                if next_start < limit {
-                    return true;
-                }
+        // The first interval starting at-or-after `start` overlaps if it starts before `limit`.
+        if let Some((next_start, _next_limit)) = self
+            .start_to_limit
+            .range((Bound::Included(start), Bound::Unbounded))
+            .next()
+        {
+            if next_start < limit {
+                return true;
             }
-            None => {}
         }
 
-        match self.get_first_limit_before(limit) {
-            Some((_prev_start, prev_limit)) => {
-                if prev_limit > start {
-                    return true;
-                }
+        // The last interval ending before `limit` overlaps if it ends after `start`.
+        if let Some((prev_limit, _prev_start)) = self
+            .limit_to_start
+            .range((Bound::Unbounded, Bound::Excluded(limit)))
+            .next_back()
+        {
+            if prev_limit > start {
+                return true;
             }
-            None => {}
         }
 
-        return false;
+        false
+    }
+
+    /// Fallible form of [`contains_partial`](Self::contains_partial): returns
+    /// [`IntervalError::InvertedBounds`] instead of panicking when `start > limit`.
+    pub fn try_contains_partial(&self, start: T, limit: T) -> Result<bool, IntervalError<T>> {
+        IntervalError::check_bounds(&start, &limit)?;
+        Ok(self.contains_partial(start, limit))
+    }
+
+    /// Returns whether all of the interval `[start, limit)` is covered; i.e. whether a single
+    /// coalesced interval in this data structure contains it.
+    ///
+    /// As with [`contains_partial`](Self::contains_partial), when the interval given is empty
+    /// this returns whether the point is contained within any interval.
+    pub fn contains_full(&self, start: T, limit: T) -> bool {
+        assert!(start <= limit);
+        if start == limit {
+            return self.find_containing(&start).is_some();
+        }
+        self.is_dominated_by_existing(&start, &limit)
+    }
+
+    /// Fallible form of [`contains_full`](Self::contains_full): returns
+    /// [`IntervalError::InvertedBounds`] instead of panicking when `start > limit`.
+    pub fn try_contains_full(&self, start: T, limit: T) -> Result<bool, IntervalError<T>> {
+        IntervalError::check_bounds(&start, &limit)?;
+        Ok(self.contains_full(start, limit))
+    }
+
+    /// Returns whether every value covered by `self` is also covered by `other`.
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.start_to_limit
+            .iter()
+            .all(|(start, limit)| other.is_dominated_by_existing(start, limit))
+    }
+
+    /// Returns whether every value covered by `other` is also covered by `self`.
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Returns whether no value is covered by both `self` and `other`.
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        // Probe the larger set with the intervals of the smaller one.
+        let (smaller, larger) = if self.start_to_limit.len() <= other.start_to_limit.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        !smaller
+            .start_to_limit
+            .iter()
+            .any(|(start, limit)| larger.overlaps(start, limit))
+    }
+
+    /// Returns an iterator over the uncovered holes within the window `[lo, hi)`, in ascending
+    /// order.
+    ///
+    /// The holes are clipped to the window; e.g. with the interval set `{[0, 1), [2, 3)}` the
+    /// window `[-1, 4)` yields `[-1, 0)`, `[1, 2)` and `[3, 4)`.
+    pub fn gaps(&self, lo: T, hi: T) -> Gaps<'_, T> {
+        assert!(lo <= hi);
+        // If an interval covers the start of the window the first hole can only begin at its
+        // limit.
+        let cursor = match self.find_containing(&lo) {
+            Some((_start, limit)) => std::cmp::min(limit, &hi).clone(),
+            None => lo.clone(),
+        };
+        // Note `BTreeMap::range` rejects an empty window with both ends excluded.
+        let after_lo = if lo == hi {
+            Bound::Included(&lo)
+        } else {
+            Bound::Excluded(&lo)
+        };
+        let intervals = self.start_to_limit.range((after_lo, Bound::Excluded(&hi)));
+        Gaps {
+            intervals,
+            cursor,
+            hi,
+            done: false,
+        }
+    }
+
+    /// Fallible form of [`gaps`](Self::gaps): returns [`IntervalError::InvertedBounds`] instead
+    /// of panicking when `lo > hi`.
+    pub fn try_gaps(&self, lo: T, hi: T) -> Result<Gaps<'_, T>, IntervalError<T>> {
+        IntervalError::check_bounds(&lo, &hi)?;
+        Ok(self.gaps(lo, hi))
+    }
+
+    /// Returns the complement of this interval set within the window `[lo, hi)`; i.e. the
+    /// holes yielded by [`gaps`](Self::gaps) as a new interval set.
+    pub fn complement_within(&self, lo: T, hi: T) -> Self {
+        Self::from_coalesced_vec(self.gaps(lo, hi).collect(), "complement_within")
+    }
+
+    /// Fallible form of [`complement_within`](Self::complement_within): returns
+    /// [`IntervalError::InvertedBounds`] instead of panicking when `lo > hi`.
+    pub fn try_complement_within(&self, lo: T, hi: T) -> Result<Self, IntervalError<T>> {
+        IntervalError::check_bounds(&lo, &hi)?;
+        Ok(self.complement_within(lo, hi))
     }
 
     /// Converts the current interval set to a vector of `[start, limit)` in sorted (ascending)
     /// order.
-    pub fn to_vec(&self) -> Vec<(T, T)> {
-        let mut v: Vec<(T, T)> = Vec::with_capacity(self.start_to_limit.len());
-        for (start, limit) in self.start_to_limit.iter() {
-            v.push((*start, *limit));
+    pub fn to_vec(&self) -> Vec<Interval<T>> {
+        self.iter().collect()
+    }
+
+    /// Returns an iterator over the `[start, limit)` intervals in sorted (ascending) order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            inner: self.start_to_limit.iter(),
+        }
+    }
+
+    /// Returns the range of starts (in `start_to_limit`) of the intervals that overlap
+    /// `[start, limit)`; see [`iter_overlapping`](Self::iter_overlapping).
+    fn overlapping_range<'a>(&'a self, start: &'a T, limit: &'a T) -> (Bound<&'a T>, Bound<&'a T>) {
+        // The first overlapping interval is either the one containing `start`, or the first one
+        // that starts after it.
+        match self.find_containing(start) {
+            Some((containing_start, _containing_limit)) if start == limit => (
+                Bound::Included(containing_start),
+                Bound::Included(containing_start),
+            ),
+            Some((containing_start, _containing_limit)) => {
+                (Bound::Included(containing_start), Bound::Excluded(limit))
+            }
+            None => (Bound::Included(start), Bound::Excluded(limit)),
+        }
+    }
+
+    /// Returns an iterator over the intervals that overlap `[start, limit)`, in sorted
+    /// (ascending) order.
+    ///
+    /// Intervals that merely abut the query window are not included. As with
+    /// [`contains_partial`](Self::contains_partial), when the query window is empty this yields
+    /// the interval containing the point `start`, if any.
+    pub fn iter_overlapping(&self, start: T, limit: T) -> Overlapping<'_, T> {
+        assert!(start <= limit);
+        Overlapping {
+            inner: self
+                .start_to_limit
+                .range(self.overlapping_range(&start, &limit)),
+        }
+    }
+
+    /// Fallible form of [`iter_overlapping`](Self::iter_overlapping): returns
+    /// [`IntervalError::InvertedBounds`] instead of panicking when `start > limit`.
+    pub fn try_iter_overlapping(
+        &self,
+        start: T,
+        limit: T,
+    ) -> Result<Overlapping<'_, T>, IntervalError<T>> {
+        IntervalError::check_bounds(&start, &limit)?;
+        Ok(self.iter_overlapping(start, limit))
+    }
+
+    /// Builds an interval set directly from intervals that are already sorted, non-empty,
+    /// disjoint and non-abutting; i.e. already maximally coalesced. `operation` names the bulk
+    /// operation that produced them, for the `paranoid` feature.
+    fn from_coalesced_vec(intervals: Vec<Interval<T>>, operation: &str) -> Self {
+        let result = CoalescedIntervals {
+            limit_to_start: intervals
+                .iter()
+                .map(|interval| (interval.limit().clone(), interval.start().clone()))
+                .collect(),
+            start_to_limit: intervals.into_iter().map(<(T, T)>::from).collect(),
+        };
+        result.paranoid_check(operation);
+        result
+    }
+
+    /// Sweeps the boundaries of `self` and `other` in ascending order and returns the maximal
+    /// runs where `keep(in_self, in_other)` holds.
+    ///
+    /// Each set contributes its boundaries as a strictly increasing sequence of points where
+    /// membership toggles (`start_0, limit_0, start_1, limit_1, ...`), so all toggles that land
+    /// on the same point are applied together; that is what keeps abutting `[a, b)` `[b, c)`
+    /// results coalesced.
+    fn merge_with(&self, other: &Self, keep: impl Fn(bool, bool) -> bool) -> Vec<Interval<T>> {
+        assert!(!keep(false, false));
+        let mut lhs = self
+            .start_to_limit
+            .iter()
+            .flat_map(|(start, limit)| [start, limit])
+            .peekable();
+        let mut rhs = other
+            .start_to_limit
+            .iter()
+            .flat_map(|(start, limit)| [start, limit])
+            .peekable();
+
+        let mut in_lhs = false;
+        let mut in_rhs = false;
+        let mut run_start: Option<&T> = None;
+        let mut result = vec![];
+        loop {
+            let point = match (lhs.peek(), rhs.peek()) {
+                (None, None) => break,
+                (Some(l), None) => *l,
+                (None, Some(r)) => *r,
+                (Some(l), Some(r)) => std::cmp::min(*l, *r),
+            };
+            if lhs.next_if_eq(&point).is_some() {
+                in_lhs = !in_lhs;
+            }
+            if rhs.next_if_eq(&point).is_some() {
+                in_rhs = !in_rhs;
+            }
+            match (run_start, keep(in_lhs, in_rhs)) {
+                (None, true) => run_start = Some(point),
+                (Some(start), false) => {
+                    result.push(Interval::new_unchecked(start.clone(), point.clone()));
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+        assert!(run_start.is_none());
+        result
+    }
+
+    /// Returns the set of values covered by either `self` or `other`.
+    pub fn union(&self, other: &Self) -> Self {
+        Self::from_coalesced_vec(self.merge_with(other, |a, b| a || b), "union")
+    }
+
+    /// Returns the set of values covered by both `self` and `other`.
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self::from_coalesced_vec(self.merge_with(other, |a, b| a && b), "intersection")
+    }
+
+    /// Returns the set of values covered by `self` but not by `other`.
+    pub fn difference(&self, other: &Self) -> Self {
+        Self::from_coalesced_vec(self.merge_with(other, |a, b| a && !b), "difference")
+    }
+
+    /// Returns the set of values covered by exactly one of `self` and `other`.
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        Self::from_coalesced_vec(
+            self.merge_with(other, |a, b| a != b),
+            "symmetric_difference",
+        )
+    }
+
+    /// In-place form of [`union`](Self::union).
+    pub fn union_with(&mut self, other: &Self) {
+        *self = self.union(other);
+    }
+
+    /// In-place form of [`intersection`](Self::intersection).
+    pub fn intersect_with(&mut self, other: &Self) {
+        *self = self.intersection(other);
+    }
+
+    /// In-place form of [`difference`](Self::difference).
+    pub fn difference_with(&mut self, other: &Self) {
+        *self = self.difference(other);
+    }
+
+    /// In-place form of [`symmetric_difference`](Self::symmetric_difference).
+    pub fn symmetric_difference_with(&mut self, other: &Self) {
+        *self = self.symmetric_difference(other);
+    }
+}
+
+/// Borrowing iterator over the intervals of a [`CoalescedIntervals`]; see
+/// [`CoalescedIntervals::iter`].
+pub struct Iter<'a, T> {
+    inner: std::collections::btree_map::Iter<'a, T, T>,
+}
+
+impl<T: Clone> Iterator for Iter<'_, T> {
+    type Item = Interval<T>;
+
+    fn next(&mut self) -> Option<Interval<T>> {
+        self.inner
+            .next()
+            .map(|(start, limit)| Interval::new_unchecked(start.clone(), limit.clone()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T: Clone> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Interval<T>> {
+        self.inner
+            .next_back()
+            .map(|(start, limit)| Interval::new_unchecked(start.clone(), limit.clone()))
+    }
+}
+
+impl<T: Clone> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T: Clone> std::iter::FusedIterator for Iter<'_, T> {}
+
+/// Owning iterator over the intervals of a [`CoalescedIntervals`].
+pub struct IntoIter<T> {
+    inner: std::collections::btree_map::IntoIter<T, T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = Interval<T>;
+
+    fn next(&mut self) -> Option<Interval<T>> {
+        self.inner
+            .next()
+            .map(|(start, limit)| Interval::new_unchecked(start, limit))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Interval<T>> {
+        self.inner
+            .next_back()
+            .map(|(start, limit)| Interval::new_unchecked(start, limit))
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> std::iter::FusedIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for CoalescedIntervals<T> {
+    type Item = Interval<T>;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter {
+            inner: self.start_to_limit.into_iter(),
+        }
+    }
+}
+
+impl<'a, T: std::cmp::Ord + Clone> IntoIterator for &'a CoalescedIntervals<T> {
+    type Item = Interval<T>;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T: std::cmp::Ord + Clone> Extend<(T, T)> for CoalescedIntervals<T> {
+    /// Adds each `(start, limit)` interval in turn; see [`add`](CoalescedIntervals::add).
+    fn extend<I: IntoIterator<Item = (T, T)>>(&mut self, iter: I) {
+        for (start, limit) in iter {
+            self.add(start, limit);
+        }
+    }
+}
+
+impl<T: std::cmp::Ord + Clone> Extend<std::ops::Range<T>> for CoalescedIntervals<T> {
+    /// Adds each `start..limit` range in turn; see [`add`](CoalescedIntervals::add).
+    fn extend<I: IntoIterator<Item = std::ops::Range<T>>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().map(|range| (range.start, range.end)));
+    }
+}
+
+impl<T: std::cmp::Ord + Clone> Extend<Interval<T>> for CoalescedIntervals<T> {
+    /// Adds each interval in turn; see [`add`](CoalescedIntervals::add).
+    fn extend<I: IntoIterator<Item = Interval<T>>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().map(<(T, T)>::from));
+    }
+}
+
+impl<T: std::cmp::Ord + Clone> FromIterator<(T, T)> for CoalescedIntervals<T> {
+    fn from_iter<I: IntoIterator<Item = (T, T)>>(iter: I) -> Self {
+        let mut result = Self::new();
+        result.extend(iter);
+        result
+    }
+}
+
+impl<T: std::cmp::Ord + Clone> FromIterator<std::ops::Range<T>> for CoalescedIntervals<T> {
+    fn from_iter<I: IntoIterator<Item = std::ops::Range<T>>>(iter: I) -> Self {
+        let mut result = Self::new();
+        result.extend(iter);
+        result
+    }
+}
+
+impl<T: std::cmp::Ord + Clone> FromIterator<Interval<T>> for CoalescedIntervals<T> {
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(iter: I) -> Self {
+        let mut result = Self::new();
+        result.extend(iter);
+        result
+    }
+}
+
+// The limit-keyed mapping mirrors the start-keyed one, so only the latter takes part in
+// comparison, hashing and formatting.
+
+impl<T: PartialEq> PartialEq for CoalescedIntervals<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.start_to_limit == other.start_to_limit
+    }
+}
+
+impl<T: Eq> Eq for CoalescedIntervals<T> {}
+
+impl<T: std::hash::Hash> std::hash::Hash for CoalescedIntervals<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.start_to_limit.hash(state);
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for CoalescedIntervals<T> {
+    /// Formats the `(start, limit)` intervals as a list in ascending order; e.g.
+    /// `[(0, 3), (5, 7)]`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.start_to_limit.iter()).finish()
+    }
+}
+
+/// Iterator over the intervals of a [`CoalescedIntervals`] that overlap a query window; see
+/// [`CoalescedIntervals::iter_overlapping`].
+pub struct Overlapping<'a, T> {
+    inner: std::collections::btree_map::Range<'a, T, T>,
+}
+
+impl<T: Clone> Iterator for Overlapping<'_, T> {
+    type Item = Interval<T>;
+
+    fn next(&mut self) -> Option<Interval<T>> {
+        self.inner
+            .next()
+            .map(|(start, limit)| Interval::new_unchecked(start.clone(), limit.clone()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T: Clone> DoubleEndedIterator for Overlapping<'_, T> {
+    fn next_back(&mut self) -> Option<Interval<T>> {
+        self.inner
+            .next_back()
+            .map(|(start, limit)| Interval::new_unchecked(start.clone(), limit.clone()))
+    }
+}
+
+impl<T: Clone> std::iter::FusedIterator for Overlapping<'_, T> {}
+
+/// Iterator over the uncovered holes of a [`CoalescedIntervals`] within a window; see
+/// [`CoalescedIntervals::gaps`].
+pub struct Gaps<'a, T> {
+    intervals: std::collections::btree_map::Range<'a, T, T>,
+    /// Start of the next hole; i.e. the limit of the last interval seen (or the window start).
+    cursor: T,
+    hi: T,
+    done: bool,
+}
+
+impl<T: std::cmp::Ord + Clone> Iterator for Gaps<'_, T> {
+    type Item = Interval<T>;
+
+    fn next(&mut self) -> Option<Interval<T>> {
+        if self.done {
+            return None;
+        }
+        // Intervals are maximally coalesced, so there is always a hole between the cursor and
+        // the next interval start.
+        if let Some((start, limit)) = self.intervals.next() {
+            let next_cursor = std::cmp::min(limit, &self.hi).clone();
+            let gap_start = std::mem::replace(&mut self.cursor, next_cursor);
+            return Some(Interval::new_unchecked(gap_start, start.clone()));
+        }
+        self.done = true;
+        if self.cursor < self.hi {
+            Some(Interval::new_unchecked(
+                self.cursor.clone(),
+                self.hi.clone(),
+            ))
+        } else {
+            None
         }
-        v
     }
 }
 
+impl<T: std::cmp::Ord + Clone> std::iter::FusedIterator for Gaps<'_, T> {}
+
+/// Implements a binary set operator (and its assigning form) in terms of the named
+/// `CoalescedIntervals` method, for all combinations of owned and borrowed operands.
+macro_rules! impl_set_operator {
+    ($op_trait:ident, $op_fn:ident, $assign_trait:ident, $assign_fn:ident, $method:ident) => {
+        impl<T: std::cmp::Ord + Clone> std::ops::$op_trait<&CoalescedIntervals<T>>
+            for &CoalescedIntervals<T>
+        {
+            type Output = CoalescedIntervals<T>;
+
+            fn $op_fn(self, rhs: &CoalescedIntervals<T>) -> CoalescedIntervals<T> {
+                self.$method(rhs)
+            }
+        }
+
+        impl<T: std::cmp::Ord + Clone> std::ops::$op_trait<CoalescedIntervals<T>>
+            for &CoalescedIntervals<T>
+        {
+            type Output = CoalescedIntervals<T>;
+
+            fn $op_fn(self, rhs: CoalescedIntervals<T>) -> CoalescedIntervals<T> {
+                self.$method(&rhs)
+            }
+        }
+
+        impl<T: std::cmp::Ord + Clone> std::ops::$op_trait<&CoalescedIntervals<T>>
+            for CoalescedIntervals<T>
+        {
+            type Output = CoalescedIntervals<T>;
+
+            fn $op_fn(self, rhs: &CoalescedIntervals<T>) -> CoalescedIntervals<T> {
+                self.$method(rhs)
+            }
+        }
+
+        impl<T: std::cmp::Ord + Clone> std::ops::$op_trait<CoalescedIntervals<T>>
+            for CoalescedIntervals<T>
+        {
+            type Output = CoalescedIntervals<T>;
+
+            fn $op_fn(self, rhs: CoalescedIntervals<T>) -> CoalescedIntervals<T> {
+                self.$method(&rhs)
+            }
+        }
+
+        impl<T: std::cmp::Ord + Clone> std::ops::$assign_trait<&CoalescedIntervals<T>>
+            for CoalescedIntervals<T>
+        {
+            fn $assign_fn(&mut self, rhs: &CoalescedIntervals<T>) {
+                *self = self.$method(rhs);
+            }
+        }
+
+        impl<T: std::cmp::Ord + Clone> std::ops::$assign_trait<CoalescedIntervals<T>>
+            for CoalescedIntervals<T>
+        {
+            fn $assign_fn(&mut self, rhs: CoalescedIntervals<T>) {
+                *self = self.$method(&rhs);
+            }
+        }
+    };
+}
+
+impl_set_operator!(BitOr, bitor, BitOrAssign, bitor_assign, union);
+impl_set_operator!(BitAnd, bitand, BitAndAssign, bitand_assign, intersection);
+impl_set_operator!(Sub, sub, SubAssign, sub_assign, difference);
+impl_set_operator!(
+    BitXor,
+    bitxor,
+    BitXorAssign,
+    bitxor_assign,
+    symmetric_difference
+);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -323,7 +1080,7 @@ mod tests {
     #[test]
     fn empty() {
         let ivals = CoalescedIntervals::<i64>::new();
-        assert_eq!(ivals.to_vec(), []);
+        assert!(ivals.to_vec().is_empty());
     }
 
     /// Adding a single interval with no area.
@@ -331,7 +1088,7 @@ mod tests {
     fn with_empty_range() {
         let mut ivals = CoalescedIntervals::<i64>::new();
         ivals.add(0, 0);
-        assert_eq!(ivals.to_vec(), []);
+        assert!(ivals.to_vec().is_empty());
         assert!(ivals.get_interval_containing(0).is_none());
         assert!(ivals.get_first_start_from(0).is_none());
     }
@@ -343,10 +1100,10 @@ mod tests {
         ivals.add(0, 1);
         assert_eq!(ivals.to_vec(), [(0, 1)]);
 
-        assert_eq!(ivals.get_first_start_from(-1), Some((0, 1)));
+        assert_eq!(ivals.get_first_start_from(-1), Some(Interval::new(0, 1)));
 
-        assert_eq!(ivals.get_interval_containing(0), Some((0, 1)));
-        assert_eq!(ivals.get_first_start_from(0), Some((0, 1)));
+        assert_eq!(ivals.get_interval_containing(0), Some(Interval::new(0, 1)));
+        assert_eq!(ivals.get_first_start_from(0), Some(Interval::new(0, 1)));
 
         assert!(ivals.get_interval_containing(1).is_none());
         assert!(ivals.get_first_start_from(1).is_none());
@@ -360,7 +1117,7 @@ mod tests {
         assert!(ivals.get_interval_containing(1).is_none());
         ivals.add(1, 2);
         assert_eq!(ivals.to_vec(), [(0, 2)]);
-        assert_eq!(ivals.get_interval_containing(1), Some((0, 2)));
+        assert_eq!(ivals.get_interval_containing(1), Some(Interval::new(0, 2)));
 
         // The coalesced interval starts from 0 so this gives us `None`.
         assert!(ivals.get_first_start_from(1).is_none());
@@ -374,7 +1131,7 @@ mod tests {
         ivals.add(0, 1);
         ivals.add(2, 3);
         assert_eq!(ivals.to_vec(), [(0, 1), (2, 3)]);
-        assert_eq!(ivals.get_first_start_from(1), Some((2, 3)));
+        assert_eq!(ivals.get_first_start_from(1), Some(Interval::new(2, 3)));
         ivals.add(1, 2);
         assert_eq!(ivals.to_vec(), [(0, 3)]);
         assert_eq!(ivals.get_first_start_from(1), None);
@@ -410,7 +1167,7 @@ mod tests {
         ivals.add(i8::MIN, i8::MIN + 1);
         assert_eq!(
             ivals.get_first_start_from(i8::MIN),
-            Some((i8::MIN, i8::MIN + 1))
+            Some(Interval::new(i8::MIN, i8::MIN + 1))
         );
     }
 
@@ -430,4 +1187,484 @@ mod tests {
         assert!(!ivals.contains_partial(3, 4));
         assert!(!ivals.contains_partial(-1, 0));
     }
+
+    /// Removing the middle of an interval splits it in two.
+    #[test]
+    fn test_remove_splits() {
+        let _ = env_logger::try_init();
+        let mut ivals = CoalescedIntervals::<i64>::new();
+        ivals.add(0, 10);
+        ivals.remove(3, 5);
+        assert_eq!(ivals.to_vec(), [(0, 3), (5, 10)]);
+        ivals.check_invariants();
+        assert!(!ivals.contains_partial(3, 5));
+        assert_eq!(ivals.get_interval_containing(5), Some(Interval::new(5, 10)));
+
+        // Adding the hole back re-coalesces the interval.
+        ivals.add(3, 5);
+        assert_eq!(ivals.to_vec(), [(0, 10)]);
+    }
+
+    /// Removing a range that overlaps the edges of several intervals trims them and drops the
+    /// intervals it dominates.
+    #[test]
+    fn test_remove_trims_edges() {
+        let mut ivals = CoalescedIntervals::<i64>::new();
+        ivals.add(0, 3);
+        ivals.add(4, 5);
+        ivals.add(6, 9);
+        ivals.remove(2, 7);
+        assert_eq!(ivals.to_vec(), [(0, 2), (7, 9)]);
+        ivals.check_invariants();
+
+        // Removing with exact edges drops the whole interval.
+        ivals.remove(7, 9);
+        assert_eq!(ivals.to_vec(), [(0, 2)]);
+        ivals.remove(-1, 3);
+        assert!(ivals.to_vec().is_empty());
+        ivals.check_invariants();
+    }
+
+    /// Removing empty ranges or ranges that fall in holes leaves the set untouched.
+    #[test]
+    fn test_remove_noop() {
+        let mut ivals = CoalescedIntervals::<i64>::new();
+        ivals.add(0, 1);
+        ivals.add(2, 3);
+        ivals.remove(1, 2);
+        ivals.remove(0, 0);
+        ivals.remove(5, 10);
+        assert_eq!(ivals.to_vec(), [(0, 1), (2, 3)]);
+        ivals.check_invariants();
+    }
+
+    /// `add` reports the parts of the interval that were not previously covered.
+    #[test]
+    fn test_add_reports_uncovered() {
+        let mut ivals = CoalescedIntervals::<i64>::new();
+        assert_eq!(ivals.add(2, 4), [(2, 4)]);
+        assert_eq!(ivals.add(6, 8), [(6, 8)]);
+        assert_eq!(ivals.add(0, 10), [(0, 2), (4, 6), (8, 10)]);
+        assert!(ivals.add(3, 7).is_empty());
+        assert!(ivals.add(5, 5).is_empty());
+        assert_eq!(ivals.add(9, 12), [(10, 12)]);
+        assert_eq!(ivals.to_vec(), [(0, 12)]);
+    }
+
+    /// `remove` reports the parts of the interval that were actually removed.
+    #[test]
+    fn test_remove_reports_removed() {
+        let mut ivals = CoalescedIntervals::<i64>::new();
+        ivals.add(0, 3);
+        ivals.add(5, 8);
+        assert!(ivals.remove(3, 5).is_empty());
+        assert_eq!(ivals.remove(1, 2), [(1, 2)]);
+        assert_eq!(ivals.remove(-1, 7), [(0, 1), (2, 3), (5, 7)]);
+        assert_eq!(ivals.to_vec(), [(7, 8)]);
+    }
+
+    fn make(intervals: &[(i64, i64)]) -> CoalescedIntervals<i64> {
+        let mut ivals = CoalescedIntervals::new();
+        for (start, limit) in intervals {
+            ivals.add(*start, *limit);
+        }
+        ivals
+    }
+
+    /// Set algebra where the operands abut at their boundaries.
+    #[test]
+    fn test_set_algebra_abutting() {
+        let lhs = make(&[(0, 2), (4, 6)]);
+        let rhs = make(&[(2, 4), (5, 8)]);
+
+        let union = lhs.union(&rhs);
+        assert_eq!(union.to_vec(), [(0, 8)]);
+        union.check_invariants();
+
+        let intersection = lhs.intersection(&rhs);
+        assert_eq!(intersection.to_vec(), [(5, 6)]);
+        intersection.check_invariants();
+
+        assert_eq!(lhs.difference(&rhs).to_vec(), [(0, 2), (4, 5)]);
+        assert_eq!(rhs.difference(&lhs).to_vec(), [(2, 4), (6, 8)]);
+
+        let symmetric_difference = lhs.symmetric_difference(&rhs);
+        assert_eq!(symmetric_difference.to_vec(), [(0, 5), (6, 8)]);
+        symmetric_difference.check_invariants();
+    }
+
+    /// Set algebra against an empty set and against itself.
+    #[test]
+    fn test_set_algebra_degenerate() {
+        let ivals = make(&[(0, 2), (4, 6)]);
+        let empty = CoalescedIntervals::new();
+        assert_eq!(ivals.union(&empty).to_vec(), ivals.to_vec());
+        assert!(ivals.intersection(&empty).to_vec().is_empty());
+        assert_eq!(ivals.difference(&empty).to_vec(), ivals.to_vec());
+        assert!(empty.difference(&ivals).to_vec().is_empty());
+        assert_eq!(ivals.union(&ivals).to_vec(), ivals.to_vec());
+        assert_eq!(ivals.intersection(&ivals).to_vec(), ivals.to_vec());
+        assert!(ivals.symmetric_difference(&ivals).to_vec().is_empty());
+    }
+
+    /// The in-place forms agree with the forms that return a new set.
+    #[test]
+    fn test_set_algebra_in_place() {
+        let rhs = make(&[(1, 3), (5, 9)]);
+
+        let mut ivals = make(&[(0, 2), (4, 6)]);
+        ivals.union_with(&rhs);
+        assert_eq!(ivals.to_vec(), [(0, 3), (4, 9)]);
+
+        let mut ivals = make(&[(0, 2), (4, 6)]);
+        ivals.intersect_with(&rhs);
+        assert_eq!(ivals.to_vec(), [(1, 2), (5, 6)]);
+
+        let mut ivals = make(&[(0, 2), (4, 6)]);
+        ivals.difference_with(&rhs);
+        assert_eq!(ivals.to_vec(), [(0, 1), (4, 5)]);
+
+        let mut ivals = make(&[(0, 2), (4, 6)]);
+        ivals.symmetric_difference_with(&rhs);
+        assert_eq!(ivals.to_vec(), [(0, 1), (2, 3), (4, 5), (6, 9)]);
+        ivals.check_invariants();
+    }
+
+    /// The operator forms agree with the named set algebra methods.
+    #[test]
+    fn test_set_operators() {
+        let lhs = make(&[(0, 2), (4, 6)]);
+        let rhs = make(&[(1, 3), (5, 9)]);
+
+        assert_eq!((&lhs | &rhs).to_vec(), lhs.union(&rhs).to_vec());
+        assert_eq!((&lhs & &rhs).to_vec(), lhs.intersection(&rhs).to_vec());
+        assert_eq!((&lhs - &rhs).to_vec(), lhs.difference(&rhs).to_vec());
+        assert_eq!(
+            (&lhs ^ &rhs).to_vec(),
+            lhs.symmetric_difference(&rhs).to_vec()
+        );
+
+        let window = make(&[(0, 5)]);
+        assert_eq!(((&lhs - &rhs) & &window).to_vec(), [(0, 1), (4, 5)]);
+        assert_eq!((make(&[(0, 2)]) | make(&[(2, 4)])).to_vec(), [(0, 4)]);
+
+        let mut ivals = make(&[(0, 2), (4, 6)]);
+        ivals |= &rhs;
+        assert_eq!(ivals.to_vec(), [(0, 3), (4, 9)]);
+        ivals -= make(&[(2, 5)]);
+        assert_eq!(ivals.to_vec(), [(0, 2), (5, 9)]);
+        ivals &= &window;
+        assert_eq!(ivals.to_vec(), [(0, 2)]);
+        ivals ^= make(&[(1, 3)]);
+        assert_eq!(ivals.to_vec(), [(0, 1), (2, 3)]);
+    }
+
+    /// Gaps are clipped to the window and skip over covered regions.
+    #[test]
+    fn test_gaps() {
+        let ivals = make(&[(0, 1), (2, 3), (5, 8)]);
+        assert_eq!(
+            ivals.gaps(-1, 10).collect::<Vec<_>>(),
+            [(-1, 0), (1, 2), (3, 5), (8, 10)]
+        );
+        assert_eq!(ivals.gaps(0, 8).collect::<Vec<_>>(), [(1, 2), (3, 5)]);
+        assert_eq!(ivals.gaps(2, 6).collect::<Vec<_>>(), [(3, 5)]);
+        assert!(ivals.gaps(4, 4).collect::<Vec<_>>().is_empty());
+        assert!(ivals.gaps(6, 7).collect::<Vec<_>>().is_empty());
+        assert_eq!(ivals.gaps(3, 4).collect::<Vec<_>>(), [(3, 4)]);
+
+        let empty = CoalescedIntervals::<i64>::new();
+        assert_eq!(empty.gaps(0, 3).collect::<Vec<_>>(), [(0, 3)]);
+    }
+
+    #[test]
+    fn test_complement_within() {
+        let ivals = make(&[(0, 1), (2, 3), (5, 8)]);
+        let holes = ivals.complement_within(0, 8);
+        assert_eq!(holes.to_vec(), [(1, 2), (3, 5)]);
+        holes.check_invariants();
+        assert_eq!(holes.union(&ivals).to_vec(), [(0, 8)]);
+        assert!(holes.intersection(&ivals).to_vec().is_empty());
+    }
+
+    #[test]
+    fn test_iter() {
+        let ivals = make(&[(0, 1), (2, 3), (5, 8)]);
+        assert_eq!(ivals.iter().collect::<Vec<_>>(), ivals.to_vec());
+        assert_eq!(ivals.iter().len(), 3);
+        assert_eq!(
+            ivals.iter().rev().collect::<Vec<_>>(),
+            [(5, 8), (2, 3), (0, 1)]
+        );
+        assert_eq!((&ivals).into_iter().next_back(), Some(Interval::new(5, 8)));
+        let mut seen = vec![];
+        for ival in &ivals {
+            seen.push(ival);
+        }
+        assert_eq!(seen, ivals.to_vec());
+        assert_eq!(
+            ivals.into_iter().rev().collect::<Vec<_>>(),
+            [(5, 8), (2, 3), (0, 1)]
+        );
+    }
+
+    /// Only intervals that overlap the window are yielded; abutting intervals are not.
+    #[test]
+    fn test_iter_overlapping() {
+        let ivals = make(&[(0, 1), (2, 3), (5, 8)]);
+        let overlapping = |start, limit| ivals.iter_overlapping(start, limit).collect::<Vec<_>>();
+        assert_eq!(overlapping(-5, 10), ivals.to_vec());
+        assert_eq!(overlapping(1, 5), [(2, 3)]);
+        assert_eq!(overlapping(0, 3), [(0, 1), (2, 3)]);
+        assert_eq!(overlapping(6, 7), [(5, 8)]);
+        assert!(overlapping(3, 5).is_empty());
+        assert!(overlapping(8, 10).is_empty());
+
+        // Empty windows behave like point queries.
+        assert_eq!(overlapping(6, 6), [(5, 8)]);
+        assert!(overlapping(4, 4).is_empty());
+
+        assert_eq!(
+            ivals.iter_overlapping(0, 6).rev().collect::<Vec<_>>(),
+            [(5, 8), (2, 3), (0, 1)]
+        );
+    }
+
+    /// Predecessor / successor queries on both edges return the nearest interval, not the
+    /// first one in the underlying map.
+    #[test]
+    fn test_nearest_edge_queries() {
+        let ivals = make(&[(0, 1), (2, 3)]);
+        assert_eq!(ivals.get_first_limit_before(10), Some(Interval::new(2, 3)));
+        assert_eq!(ivals.get_first_limit_before(3), Some(Interval::new(0, 1)));
+        assert_eq!(ivals.get_first_limit_before(1), None);
+
+        assert_eq!(ivals.get_last_start_before(10), Some(Interval::new(2, 3)));
+        assert_eq!(ivals.get_last_start_before(2), Some(Interval::new(2, 3)));
+        assert_eq!(ivals.get_last_start_before(1), Some(Interval::new(0, 1)));
+        assert_eq!(ivals.get_last_start_before(-1), None);
+
+        assert_eq!(ivals.get_first_limit_from(-10), Some(Interval::new(0, 1)));
+        assert_eq!(ivals.get_first_limit_from(1), Some(Interval::new(0, 1)));
+        assert_eq!(ivals.get_first_limit_from(2), Some(Interval::new(2, 3)));
+        assert_eq!(ivals.get_first_limit_from(4), None);
+    }
+
+    /// A query window that overlaps only the tail of a later interval, with an earlier interval
+    /// further to the left.
+    #[test]
+    fn test_contains_partial_with_earlier_interval() {
+        let ivals = make(&[(0, 1), (5, 8)]);
+        assert!(ivals.contains_partial(6, 10));
+        assert!(ivals.contains_partial(7, 8));
+        assert!(!ivals.contains_partial(8, 10));
+        assert!(!ivals.contains_partial(1, 5));
+    }
+
+    /// The fallible forms report inverted bounds rather than panicking, and otherwise agree with
+    /// the panicking forms.
+    #[test]
+    fn test_try_inverted_bounds() {
+        let mut ivals = make(&[(0, 3)]);
+        let inverted = IntervalError::InvertedBounds { start: 2, limit: 1 };
+        assert_eq!(ivals.try_add(2, 1), Err(inverted));
+        assert_eq!(ivals.try_remove(2, 1), Err(inverted));
+        assert_eq!(ivals.try_contains_partial(2, 1), Err(inverted));
+        assert!(ivals.try_gaps(2, 1).is_err());
+        assert!(ivals.try_iter_overlapping(2, 1).is_err());
+        assert!(ivals.try_complement_within(2, 1).is_err());
+        assert_eq!(ivals.to_vec(), [(0, 3)]);
+
+        assert_eq!(ivals.try_add(2, 5), Ok(vec![Interval::new(3, 5)]));
+        assert_eq!(ivals.try_remove(1, 2), Ok(vec![Interval::new(1, 2)]));
+        assert_eq!(ivals.try_contains_partial(1, 2), Ok(false));
+        assert_eq!(
+            ivals.try_gaps(0, 6).unwrap().collect::<Vec<_>>(),
+            [(1, 2), (5, 6)]
+        );
+        assert_eq!(
+            IntervalError::InvertedBounds { start: 2, limit: 1 }.to_string(),
+            "inverted interval bounds: start 2 > limit 1"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut ivals = make(&[(0, 2), (4, 6)]);
+        assert_eq!(ivals.validate(), Ok(()));
+
+        // Corrupt the internals in various ways and check the violation names the culprits.
+        ivals.insert_record(2, 4);
+        assert_eq!(
+            ivals.validate(),
+            Err(InvariantViolation::Abutting {
+                first: Interval::new(0, 2),
+                second: Interval::new(2, 4)
+            })
+        );
+        ivals.remove_with_start_at(&2);
+
+        ivals.insert_record(5, 8);
+        assert_eq!(
+            ivals.validate(),
+            Err(InvariantViolation::Overlapping {
+                first: Interval::new(4, 6),
+                second: Interval::new(5, 8)
+            })
+        );
+        ivals.remove_with_start_at(&5);
+
+        ivals.limit_to_start.insert(10, 9);
+        assert_eq!(
+            ivals.validate(),
+            Err(InvariantViolation::Asymmetric {
+                start: 9,
+                limit: 10
+            })
+        );
+        ivals.limit_to_start.remove(&10);
+
+        ivals.insert_record(8, 8);
+        assert_eq!(
+            ivals.validate(),
+            Err(InvariantViolation::Empty { start: 8, limit: 8 })
+        );
+        ivals.remove_with_start_at(&8);
+        assert_eq!(ivals.validate(), Ok(()));
+    }
+
+    #[test]
+    #[should_panic(expected = "intervals [0, 2) and [2, 4) abut")]
+    fn test_check_invariants_panics() {
+        let mut ivals = make(&[(0, 2)]);
+        ivals.insert_record(2, 4);
+        ivals.check_invariants();
+    }
+
+    #[test]
+    fn test_std_traits() {
+        let ivals: CoalescedIntervals<i64> = [(0, 2), (5, 7), (2, 3)].into_iter().collect();
+        assert_eq!(ivals.to_vec(), [(0, 3), (5, 7)]);
+        assert_eq!(format!("{:?}", ivals), "[(0, 3), (5, 7)]");
+        assert_eq!(format!("{:?}", CoalescedIntervals::<i64>::default()), "[]");
+
+        // The same set built differently compares (and hashes) equal.
+        let from_ranges: CoalescedIntervals<i64> = vec![5..7, 0..1, 1..3].into_iter().collect();
+        assert_eq!(ivals, from_ranges);
+        let mut sets = std::collections::HashMap::new();
+        sets.insert(ivals.clone(), "first");
+        assert_eq!(sets.get(&from_ranges), Some(&"first"));
+
+        let mut extended = ivals.clone();
+        extended.extend(std::iter::once(3..5));
+        assert_ne!(extended, ivals);
+        extended.extend([(-1, 0), (7, 7)]);
+        assert_eq!(extended.to_vec(), [(-1, 7)]);
+        extended.check_invariants();
+        // Cloning is deep.
+        assert_eq!(ivals.to_vec(), [(0, 3), (5, 7)]);
+    }
+
+    #[test]
+    fn test_contains_full() {
+        let ivals = make(&[(0, 5), (10, 15)]);
+        assert!(ivals.contains_full(0, 5));
+        assert!(ivals.contains_full(11, 14));
+        assert!(!ivals.contains_full(3, 12));
+        assert!(!ivals.contains_full(4, 6));
+        assert!(!ivals.contains_full(5, 10));
+        // Empty intervals ask about the point, as with `contains_partial`.
+        assert!(ivals.contains_full(4, 4));
+        assert!(!ivals.contains_full(5, 5));
+        assert_eq!(
+            ivals.try_contains_full(3, 2),
+            Err(IntervalError::InvertedBounds { start: 3, limit: 2 })
+        );
+    }
+
+    #[test]
+    fn test_subset_superset_disjoint() {
+        let outer = make(&[(0, 10), (20, 30)]);
+        let inner = make(&[(2, 4), (5, 10), (25, 26)]);
+        let straddling = make(&[(8, 22)]);
+        let between = make(&[(10, 20), (40, 50)]);
+        let empty = make(&[]);
+
+        assert!(inner.is_subset(&outer));
+        assert!(outer.is_superset(&inner));
+        assert!(!outer.is_subset(&inner));
+        assert!(!straddling.is_subset(&outer));
+        assert!(outer.is_subset(&outer));
+        assert!(empty.is_subset(&outer));
+        assert!(outer.is_superset(&empty));
+
+        // Abutting intervals share no values.
+        assert!(outer.is_disjoint(&between));
+        assert!(between.is_disjoint(&outer));
+        assert!(!outer.is_disjoint(&straddling));
+        assert!(!straddling.is_disjoint(&outer));
+        assert!(empty.is_disjoint(&outer));
+        assert!(!outer.is_disjoint(&outer));
+    }
+
+    /// Keys only need to be `Ord + Clone`; e.g. lexicographic key spans.
+    #[test]
+    fn test_string_keys() {
+        let key = |s: &str| s.to_string();
+        let mut spans = CoalescedIntervals::<String>::new();
+        spans.add(key("apple"), key("banana"));
+        spans.add(key("cherry"), key("date"));
+        assert_eq!(
+            spans.add(key("avocado"), key("cherry")),
+            [(key("banana"), key("cherry"))]
+        );
+        assert_eq!(spans.to_vec(), [(key("apple"), key("date"))]);
+        assert_eq!(spans.remove(key("b"), key("c")), [(key("b"), key("c"))]);
+        assert_eq!(
+            spans.to_vec(),
+            [(key("apple"), key("b")), (key("c"), key("date"))]
+        );
+        assert!(!spans.contains_partial(key("b"), key("bz")));
+        assert_eq!(
+            spans.gaps(key("a"), key("e")).collect::<Vec<_>>(),
+            [
+                (key("a"), key("apple")),
+                (key("b"), key("c")),
+                (key("date"), key("e"))
+            ]
+        );
+        spans.check_invariants();
+
+        let mut byte_spans = CoalescedIntervals::<Vec<u8>>::new();
+        byte_spans.add(vec![0x00], vec![0x10]);
+        byte_spans.add(vec![0x10], vec![0x10, 0x00]);
+        assert_eq!(byte_spans.to_vec(), [(vec![0x00], vec![0x10, 0x00])]);
+    }
+
+    /// Nothing other than `check_invariants` requires `T: Debug`, with or without the `paranoid`
+    /// feature.
+    #[test]
+    fn test_non_debug_keys() {
+        #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+        struct Key(u32);
+
+        let mut ivals = CoalescedIntervals::<Key>::new();
+        ivals.add(Key(0), Key(2));
+        ivals.add(Key(2), Key(4));
+        ivals.remove(Key(1), Key(3));
+        assert_eq!(ivals.iter().count(), 2);
+        assert!(ivals.validate().is_ok());
+        let both = &ivals | &ivals;
+        assert_eq!(both.iter().count(), 2);
+    }
+
+    /// In paranoid mode a mutation on top of corrupted internals panics, naming the operation.
+    #[cfg(feature = "paranoid")]
+    #[test]
+    #[should_panic(expected = "violated after add: intervals abut but were not coalesced")]
+    fn test_paranoid_names_operation() {
+        let mut ivals = make(&[(0, 2)]);
+        ivals.insert_record(2, 4);
+        ivals.add(10, 12);
+    }
 }
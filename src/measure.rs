@@ -0,0 +1,167 @@
+use std::time::{Duration, Instant};
+
+use crate::{CoalescedIntervals, Interval, IntervalError};
+
+/// Endpoint types with a notion of distance, which lets interval sets measure how much of the
+/// domain they cover; see [`CoalescedIntervals::total_len`].
+pub trait Measure {
+    /// Type of the distance between two endpoints; e.g. `u64` for `i64` endpoints, so that the
+    /// length of `[i64::MIN, i64::MAX)` does not overflow.
+    ///
+    /// `Default` gives the zero length.
+    type Length: Copy
+        + Default
+        + Ord
+        + std::ops::Add<Output = Self::Length>
+        + std::ops::Sub<Output = Self::Length>;
+
+    /// Returns the distance from `start` to `limit`, where `start <= limit`.
+    fn distance(start: &Self, limit: &Self) -> Self::Length;
+
+    /// Returns the endpoint `distance` past `start`; i.e. the inverse of
+    /// [`distance`](Self::distance). The result must be representable.
+    fn offset(start: &Self, distance: Self::Length) -> Self;
+}
+
+macro_rules! impl_measure_for_integer {
+    ($($t:ty => $length:ty),*) => {
+        $(
+            impl Measure for $t {
+                type Length = $length;
+
+                fn distance(start: &Self, limit: &Self) -> $length {
+                    limit.abs_diff(*start)
+                }
+
+                fn offset(start: &Self, distance: $length) -> Self {
+                    // Signed starts wrap through the unsigned type, which lands back in range.
+                    (*start as $length).wrapping_add(distance) as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_measure_for_integer!(
+    u8 => u8, u16 => u16, u32 => u32, u64 => u64, u128 => u128, usize => usize,
+    i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128, isize => usize
+);
+
+impl Measure for Duration {
+    type Length = Duration;
+
+    fn distance(start: &Self, limit: &Self) -> Duration {
+        *limit - *start
+    }
+
+    fn offset(start: &Self, distance: Duration) -> Self {
+        *start + distance
+    }
+}
+
+impl Measure for Instant {
+    type Length = Duration;
+
+    fn distance(start: &Self, limit: &Self) -> Duration {
+        limit.duration_since(*start)
+    }
+
+    fn offset(start: &Self, distance: Duration) -> Self {
+        *start + distance
+    }
+}
+
+impl<T: Measure> Interval<T> {
+    /// Returns the distance from the start of the interval to its limit.
+    pub fn len(&self) -> T::Length {
+        T::distance(self.start(), self.limit())
+    }
+}
+
+impl<T: std::cmp::Ord + Clone + Measure> CoalescedIntervals<T> {
+    /// Returns the total length covered by the current interval set.
+    pub fn total_len(&self) -> T::Length {
+        self.start_to_limit
+            .iter()
+            .fold(T::Length::default(), |total, (start, limit)| {
+                total + T::distance(start, limit)
+            })
+    }
+
+    /// Returns the length of the window `[start, limit)` that is covered by the current interval
+    /// set; i.e. intervals that straddle either edge of the window only count the part of them
+    /// inside it.
+    ///
+    /// This walks the intervals in the window; see [`IndexedIntervals`](crate::IndexedIntervals)
+    /// for an O(log n) form.
+    pub fn covered_len_in(&self, start: T, limit: T) -> T::Length {
+        assert!(start <= limit);
+        self.start_to_limit
+            .range(self.overlapping_range(&start, &limit))
+            .fold(
+                T::Length::default(),
+                |total, (existing_start, existing_limit)| {
+                    total
+                        + T::distance(
+                            std::cmp::max(existing_start, &start),
+                            std::cmp::min(existing_limit, &limit),
+                        )
+                },
+            )
+    }
+
+    /// Fallible form of [`covered_len_in`](Self::covered_len_in): returns
+    /// [`IntervalError::InvertedBounds`] instead of panicking when `start > limit`.
+    pub fn try_covered_len_in(&self, start: T, limit: T) -> Result<T::Length, IntervalError<T>> {
+        IntervalError::check_bounds(&start, &limit)?;
+        Ok(self.covered_len_in(start, limit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integer_lengths() {
+        let ivals: CoalescedIntervals<i64> = [(0, 10), (20, 25), (30, 40)].into_iter().collect();
+        assert_eq!(ivals.total_len(), 25);
+        assert_eq!(ivals.covered_len_in(5, 35), 15);
+        assert_eq!(ivals.covered_len_in(10, 20), 0);
+        assert_eq!(ivals.covered_len_in(22, 22), 0);
+        assert_eq!(ivals.covered_len_in(-100, 100), 25);
+        assert_eq!(
+            ivals.try_covered_len_in(1, 0),
+            Err(IntervalError::InvertedBounds { start: 1, limit: 0 })
+        );
+        assert_eq!(CoalescedIntervals::<u8>::new().total_len(), 0);
+
+        // Signed endpoints measure in the unsigned type, so the whole domain fits.
+        let mut whole = CoalescedIntervals::<i8>::new();
+        whole.add(i8::MIN, i8::MAX);
+        assert_eq!(whole.total_len(), u8::MAX);
+        assert_eq!(Interval::new(-3i8, 4).len(), 7u8);
+        assert_eq!(i8::offset(&i8::MIN, u8::MAX), i8::MAX);
+        assert_eq!(i8::offset(&-3, 7), 4);
+    }
+
+    #[test]
+    fn test_time_lengths() {
+        let secs = Duration::from_secs;
+        let mut busy = CoalescedIntervals::<Duration>::new();
+        busy.add(secs(0), secs(2));
+        busy.add(secs(5), secs(6));
+        assert_eq!(busy.total_len(), secs(3));
+        assert_eq!(busy.covered_len_in(secs(1), secs(10)), secs(2));
+
+        let epoch = Instant::now();
+        let mut uptime = CoalescedIntervals::<Instant>::new();
+        uptime.add(epoch, epoch + secs(60));
+        uptime.add(epoch + secs(120), epoch + secs(180));
+        assert_eq!(uptime.total_len(), secs(120));
+        assert_eq!(
+            uptime.covered_len_in(epoch + secs(30), epoch + secs(150)),
+            secs(60)
+        );
+    }
+}
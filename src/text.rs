@@ -0,0 +1,314 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{CoalescedIntervals, ParseError, ParseErrorReason};
+
+/// Characters that end an unquoted endpoint.
+const DELIMITERS: [char; 4] = [',', ')', ']', '}'];
+
+/// Formats the interval set as e.g. `{[0, 3), [5, 7)}`; the empty set is `{}`.
+///
+/// An endpoint whose text would not parse back unquoted (e.g. the string `a,b`, or one with
+/// leading or trailing whitespace) is written in double quotes, with `"` and `\` escaped by a
+/// backslash: `{["a,b", c)}`.
+impl<T: fmt::Display> fmt::Display for CoalescedIntervals<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, (start, limit)) in self.start_to_limit.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "[")?;
+            write_endpoint(f, start)?;
+            write!(f, ", ")?;
+            write_endpoint(f, limit)?;
+            write!(f, ")")?;
+        }
+        write!(f, "}}")
+    }
+}
+
+/// Writes `value`, quoting it if its text would be ambiguous unquoted.
+fn write_endpoint(f: &mut fmt::Formatter<'_>, value: &impl fmt::Display) -> fmt::Result {
+    let text = value.to_string();
+    let needs_quotes = text.is_empty()
+        || text.starts_with('"')
+        || text.trim() != text
+        || text.contains(DELIMITERS);
+    if !needs_quotes {
+        return f.write_str(&text);
+    }
+    write!(f, "\"")?;
+    for c in text.chars() {
+        if c == '"' || c == '\\' {
+            write!(f, "\\")?;
+        }
+        write!(f, "{}", c)?;
+    }
+    write!(f, "\"")
+}
+
+/// Parses the format produced by [`Display`](fmt::Display), e.g. `{[0, 3), [5, 7)}`, including
+/// its quoted endpoints.
+///
+/// The intervals may be given in any order and may overlap, abut or be empty; they are coalesced
+/// as though added one at a time. See
+/// [`from_str_strict`](CoalescedIntervals::from_str_strict) to reject overlapping input.
+impl<T> FromStr for CoalescedIntervals<T>
+where
+    T: Ord + Clone + FromStr,
+    T::Err: fmt::Display,
+{
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        parse(s, false)
+    }
+}
+
+impl<T> CoalescedIntervals<T>
+where
+    T: Ord + Clone + FromStr,
+    T::Err: fmt::Display,
+{
+    /// Strict form of [`from_str`](FromStr::from_str): returns
+    /// [`ParseErrorReason::Overlapping`] if any interval overlaps one given before it.
+    ///
+    /// Intervals that merely abut are still accepted and coalesced.
+    pub fn from_str_strict(s: &str) -> Result<Self, ParseError> {
+        parse(s, true)
+    }
+}
+
+fn parse<T>(s: &str, strict: bool) -> Result<CoalescedIntervals<T>, ParseError>
+where
+    T: Ord + Clone + FromStr,
+    T::Err: fmt::Display,
+{
+    let mut parser = Parser { input: s, pos: 0 };
+    let mut result = CoalescedIntervals::new();
+    parser.expect('{', "'{'")?;
+    if !parser.eat('}') {
+        loop {
+            let interval_pos = parser.skip_whitespace();
+            parser.expect('[', "'['")?;
+            let start: T = parser.endpoint()?;
+            parser.expect(',', "','")?;
+            let limit: T = parser.endpoint()?;
+            parser.expect(')', "')'")?;
+            let error = |reason| ParseError {
+                position: interval_pos,
+                reason,
+            };
+            if start > limit {
+                return Err(error(ParseErrorReason::InvertedBounds));
+            }
+            if strict && start < limit && result.contains_partial(start.clone(), limit.clone()) {
+                return Err(error(ParseErrorReason::Overlapping));
+            }
+            result.add(start, limit);
+            if parser.eat('}') {
+                break;
+            }
+            parser.expect(',', "',' or '}'")?;
+        }
+    }
+    if parser.skip_whitespace() != s.len() {
+        return Err(ParseError {
+            position: parser.pos,
+            reason: ParseErrorReason::TrailingInput,
+        });
+    }
+    Ok(result)
+}
+
+/// Cursor over the input text; `pos` is a byte offset.
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    /// Advances past any whitespace, returning the new position.
+    fn skip_whitespace(&mut self) -> usize {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+        self.pos
+    }
+
+    /// Consumes `token` (after any whitespace) if it is next, returning whether it was.
+    fn eat(&mut self, token: char) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(token) {
+            self.pos += token.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consumes `token` (after any whitespace), or reports that `description` was expected.
+    fn expect(&mut self, token: char, description: &'static str) -> Result<(), ParseError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(ParseError {
+                position: self.pos,
+                reason: ParseErrorReason::Expected(description),
+            })
+        }
+    }
+
+    /// Parses the endpoint text up to the next delimiter, ignoring surrounding whitespace, or
+    /// the quoted endpoint text if it starts with `"`.
+    fn endpoint<T>(&mut self) -> Result<T, ParseError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let start = self.skip_whitespace();
+        let parsed = if self.rest().starts_with('"') {
+            self.quoted()?.parse()
+        } else {
+            let len = self.rest().find(DELIMITERS).unwrap_or(self.rest().len());
+            let text = self.rest()[..len].trim_end();
+            self.pos += len;
+            text.parse()
+        };
+        parsed.map_err(|e: T::Err| ParseError {
+            position: start,
+            reason: ParseErrorReason::InvalidEndpoint(e.to_string()),
+        })
+    }
+
+    /// Consumes a double-quoted string, returning its text with the backslash escapes removed.
+    fn quoted(&mut self) -> Result<String, ParseError> {
+        let mut text = String::new();
+        let mut escaped = false;
+        for (offset, c) in self.rest().char_indices().skip(1) {
+            match c {
+                _ if escaped => {
+                    text.push(c);
+                    escaped = false;
+                }
+                '\\' => escaped = true,
+                '"' => {
+                    self.pos += offset + 1;
+                    return Ok(text);
+                }
+                _ => text.push(c),
+            }
+        }
+        self.pos = self.input.len();
+        Err(ParseError {
+            position: self.pos,
+            reason: ParseErrorReason::Expected("closing '\"'"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let ivals: CoalescedIntervals<i64> = [(5, 7), (0, 3)].into_iter().collect();
+        assert_eq!(ivals.to_string(), "{[0, 3), [5, 7)}");
+        assert_eq!(ivals.to_string().parse(), Ok(ivals));
+        assert_eq!(CoalescedIntervals::<i64>::new().to_string(), "{}");
+        assert_eq!(" { } ".parse(), Ok(CoalescedIntervals::<i64>::new()));
+
+        // Unordered, overlapping, abutting and empty input is coalesced.
+        let parsed: CoalescedIntervals<i64> =
+            "{[4,6), [-2, 1), [0, 2), [2,2), [6, 7)}".parse().unwrap();
+        assert_eq!(parsed.to_vec(), [(-2, 2), (4, 7)]);
+    }
+
+    /// Endpoints whose text holds delimiters, quotes or surrounding whitespace are quoted.
+    #[test]
+    fn test_quoted_round_trip() {
+        let ivals: CoalescedIntervals<String> = [
+            ("a,b", "b)"),
+            ("", " d"),
+            ("c\\d", "q\"uote"),
+            ("r", "s,\"\\t"),
+        ]
+        .into_iter()
+        .map(|(start, limit)| (start.to_string(), limit.to_string()))
+        .collect();
+        assert_eq!(
+            ivals.to_string(),
+            r#"{["", " d"), ["a,b", "b)"), [c\d, q"uote), [r, "s,\"\\t")}"#
+        );
+        assert_eq!(ivals.to_string().parse(), Ok(ivals));
+        assert_eq!(
+            "{[\"1\", 2)}"
+                .parse::<CoalescedIntervals<i64>>()
+                .unwrap()
+                .to_vec(),
+            [(1, 2)]
+        );
+        assert_eq!(
+            "{[\"a, b)}".parse::<CoalescedIntervals<String>>(),
+            Err(ParseError {
+                position: 9,
+                reason: ParseErrorReason::Expected("closing '\"'"),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let parse = |s: &str| s.parse::<CoalescedIntervals<i64>>().unwrap_err();
+        let error = |position, reason| ParseError { position, reason };
+        assert_eq!(parse("[0, 1)"), error(0, ParseErrorReason::Expected("'{'")));
+        assert_eq!(
+            parse("{[0, 1) [2, 3)}"),
+            error(8, ParseErrorReason::Expected("',' or '}'"))
+        );
+        assert_eq!(
+            parse("{[0, 1]}"),
+            error(6, ParseErrorReason::Expected("')'"))
+        );
+        assert_eq!(
+            parse("{[0, 1), [3, 2)}"),
+            error(9, ParseErrorReason::InvertedBounds)
+        );
+        assert_eq!(
+            parse("{[0, 1)} x"),
+            error(9, ParseErrorReason::TrailingInput)
+        );
+        let invalid = parse("{[0, x)}");
+        assert_eq!(invalid.position, 5);
+        assert!(matches!(
+            invalid.reason,
+            ParseErrorReason::InvalidEndpoint(_)
+        ));
+        assert_eq!(
+            invalid.to_string(),
+            "invalid interval set at position 5: invalid endpoint: invalid digit found in string"
+        );
+    }
+
+    #[test]
+    fn test_strict() {
+        assert_eq!(
+            CoalescedIntervals::<i64>::from_str_strict("{[0, 2), [2, 4), [6, 6)}")
+                .unwrap()
+                .to_vec(),
+            [(0, 4)]
+        );
+        assert_eq!(
+            CoalescedIntervals::<i64>::from_str_strict("{[0, 2), [5, 8), [1, 3)}"),
+            Err(ParseError {
+                position: 17,
+                reason: ParseErrorReason::Overlapping,
+            })
+        );
+    }
+}